@@ -1 +1,100 @@
+use crate::client::{decode, Octokit};
+use crate::error::OctokitError;
+use crate::{
+    ApiPreviews, GithubApp, Installation, InstallationToken, InstallationTokenRequest, ID,
+};
 
+impl Octokit {
+    ///  curl -i -H "Authorization: Bearer YOUR_JWT"
+    ///          -H "Accept: application/vnd.github.machine-man-preview+json"
+    ///          https://api.github.com/app
+    pub async fn get_app(&self) -> Result<GithubApp, OctokitError> {
+        decode(self.get(self.url("/app")).await?).await
+    }
+
+    /// GET /app/installations/:installation_id
+    pub async fn get_installation(
+        &self,
+        installation_id: ID,
+    ) -> Result<Installation, OctokitError> {
+        decode(
+            self.get(self.url(&format!("/app/installations/{}", installation_id)))
+                .await?,
+        )
+        .await
+    }
+
+    /// POST /app/installations/:installation_id/access_tokens
+    ///
+    /// Pass `InstallationTokenRequest::default()` for a token with all
+    /// permissions of the installation. See [`AppAuth`](auth/struct.AppAuth.html)
+    /// for clients that create and refresh installation tokens on their own.
+    pub async fn create_installation_token(
+        &self,
+        installation_id: ID,
+        request: &InstallationTokenRequest,
+    ) -> Result<InstallationToken, OctokitError> {
+        decode(
+            self.post(
+                self.url(&format!(
+                    "/app/installations/{}/access_tokens",
+                    installation_id
+                )),
+                request,
+                ApiPreviews::MachineMan,
+            )
+            .await?,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InstallationPermissions, PermissionGrant};
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn requests_scoped_installation_tokens() {
+        let mock = mockito::mock("POST", "/app/installations/7/access_tokens")
+            .match_body(Matcher::Json(serde_json::json!({
+                "repository_ids": [1296269],
+                "permissions": {"checks": "write", "actions": "read"}
+            })))
+            .with_status(201)
+            .with_body(
+                r#"{"token": "ghs_scoped", "expires_at": "2016-07-11T22:14:10Z",
+                    "permissions": {"checks": "write", "actions": "read"},
+                    "repository_selection": "selected",
+                    "repositories": [{"full_name": "octocat/Hello-World"}]}"#,
+            )
+            .create();
+
+        let octokit = crate::testing::mock_client();
+        let mut other = std::collections::BTreeMap::new();
+        other.insert("actions".to_string(), PermissionGrant::Read);
+        let request = InstallationTokenRequest {
+            repository_ids: Some(vec![1296269]),
+            permissions: Some(InstallationPermissions {
+                checks: Some(PermissionGrant::Write),
+                other,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let token = octokit
+            .create_installation_token(7, &request)
+            .await
+            .unwrap();
+
+        assert_eq!(token.token, "ghs_scoped");
+        assert_eq!(token.permissions.checks, Some(PermissionGrant::Write));
+        assert_eq!(token.permissions.other["actions"], PermissionGrant::Read);
+        assert_eq!(
+            token.repositories.unwrap()[0].full_name,
+            "octocat/Hello-World"
+        );
+        mock.assert();
+    }
+}
@@ -0,0 +1,222 @@
+//! The OAuth device flow, for command line tools that cannot receive a redirect.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), octokit::error::OctokitError> {
+//! use octokit::auth::device::DeviceFlow;
+//!
+//! let flow = DeviceFlow::new("client-id");
+//! let code = flow.request_code(&["repo"]).await?;
+//! println!("Open {} and enter {}", code.verification_uri, code.user_code);
+//! let token = flow.poll(&code).await?;
+//! let octokit = octokit::Octokit::new(token.access_token)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::oauth::{post_form, OAuthError, OAuthToken};
+use super::DEFAULT_WEB_URL;
+use crate::error::OctokitError;
+use crate::URI;
+
+const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+/// Added to the polling interval whenever GitHub answers `slow_down` without a new interval.
+const SLOW_DOWN_INCREMENT: u64 = 5;
+
+/// Drives the device flow of an OAuth app or GitHub App. Only the client id
+/// is needed, the flow has to be enabled in the app settings.
+#[derive(Debug, Clone)]
+pub struct DeviceFlow {
+    client_id: String,
+    web_url: URI,
+    http: reqwest::Client,
+}
+
+/// Response of `POST /login/device/code`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeviceCode {
+    pub device_code: String,
+    /// The code the user has to enter at `verification_uri`.
+    pub user_code: String,
+    pub verification_uri: URI,
+    /// Seconds until `device_code` and `user_code` expire.
+    pub expires_in: u64,
+    /// Minimum number of seconds between two polls.
+    pub interval: u64,
+}
+
+#[derive(Serialize, Debug)]
+struct CodeRequest<'a> {
+    client_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct PollRequest<'a> {
+    client_id: &'a str,
+    device_code: &'a str,
+    grant_type: &'a str,
+}
+
+impl DeviceFlow {
+    pub fn new(client_id: impl Into<String>) -> DeviceFlow {
+        DeviceFlow {
+            client_id: client_id.into(),
+            web_url: DEFAULT_WEB_URL.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends the requests to `url` instead of `https://github.com`.
+    pub fn web_url(mut self, url: impl Into<String>) -> Self {
+        self.web_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// POST /login/device/code
+    ///
+    /// Asks for a user code to present to the user along with the verification URI.
+    pub async fn request_code(&self, scopes: &[&str]) -> Result<DeviceCode, OctokitError> {
+        let request = CodeRequest {
+            client_id: &self.client_id,
+            scope: if scopes.is_empty() {
+                None
+            } else {
+                Some(scopes.join(" "))
+            },
+        };
+        post_form(
+            &self.http,
+            &format!("{}/login/device/code", self.web_url),
+            &request,
+        )
+        .await
+    }
+
+    /// POST /login/oauth/access_token
+    ///
+    /// Polls until the user entered the code, returning the token. Fails with
+    /// [`OctokitError::OAuth`](../../error/enum.OctokitError.html) once the user
+    /// denied access (`access_denied`) or the code expired (`expired_token`).
+    pub async fn poll(&self, code: &DeviceCode) -> Result<OAuthToken, OctokitError> {
+        let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+        let mut interval = code.interval;
+        let request = PollRequest {
+            client_id: &self.client_id,
+            device_code: &code.device_code,
+            grant_type: DEVICE_GRANT_TYPE,
+        };
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if Instant::now() >= deadline {
+                return Err(OctokitError::OAuth(OAuthError {
+                    error: "expired_token".to_string(),
+                    error_description: Some("the device code expired while polling".to_string()),
+                    error_uri: None,
+                    interval: None,
+                }));
+            }
+
+            let err = match post_form(
+                &self.http,
+                &format!("{}/login/oauth/access_token", self.web_url),
+                &request,
+            )
+            .await
+            {
+                Err(OctokitError::OAuth(err)) => err,
+                result => return result,
+            };
+            match err.error.as_str() {
+                "authorization_pending" => debug!("waiting for the user to enter the device code"),
+                "slow_down" => {
+                    interval = err.interval.unwrap_or(interval + SLOW_DOWN_INCREMENT);
+                    debug!("polling too fast, slowing down to {}s", interval);
+                }
+                _ => return Err(OctokitError::OAuth(err)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    fn flow() -> DeviceFlow {
+        DeviceFlow::new("Iv1.device").web_url(mockito::server_url())
+    }
+
+    fn device_code(device_code: &str) -> DeviceCode {
+        DeviceCode {
+            device_code: device_code.to_string(),
+            user_code: "WDJB-MJHT".to_string(),
+            verification_uri: "https://github.com/login/device".to_string(),
+            expires_in: 900,
+            interval: 0,
+        }
+    }
+
+    fn poll_mock(device_code: &str, body: &str) -> mockito::Mock {
+        mockito::mock("POST", "/login/oauth/access_token")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("device_code".into(), device_code.into()),
+                Matcher::UrlEncoded("grant_type".into(), DEVICE_GRANT_TYPE.into()),
+            ]))
+            .with_body(body)
+            .expect(1)
+            .create()
+    }
+
+    #[tokio::test]
+    async fn requests_device_codes() {
+        let _mock = mockito::mock("POST", "/login/device/code")
+            .match_body("client_id=Iv1.device&scope=repo+read%3Aorg")
+            .with_body(
+                r#"{"device_code": "3584d83530557fdd1f46af8289938c8ef79f9dc5",
+                    "user_code": "WDJB-MJHT", "verification_uri": "https://github.com/login/device",
+                    "expires_in": 900, "interval": 5}"#,
+            )
+            .create();
+
+        let code = flow().request_code(&["repo", "read:org"]).await.unwrap();
+
+        assert_eq!(code.user_code, "WDJB-MJHT");
+        assert_eq!(code.interval, 5);
+    }
+
+    #[tokio::test]
+    async fn polls_until_the_user_authorized_the_device() {
+        let pending = poll_mock("granted", r#"{"error": "authorization_pending"}"#);
+        let slow_down = poll_mock("granted", r#"{"error": "slow_down", "interval": 0}"#);
+        let granted = poll_mock(
+            "granted",
+            r#"{"access_token": "gho_device", "token_type": "bearer", "scope": "repo"}"#,
+        );
+
+        let token = flow().poll(&device_code("granted")).await.unwrap();
+
+        assert_eq!(token.access_token, "gho_device");
+        pending.assert();
+        slow_down.assert();
+        granted.assert();
+    }
+
+    #[tokio::test]
+    async fn stops_polling_when_access_is_denied() {
+        let _denied = poll_mock(
+            "denied",
+            r#"{"error": "access_denied", "error_description": "The user has denied your application access."}"#,
+        );
+
+        match flow().poll(&device_code("denied")).await {
+            Err(OctokitError::OAuth(err)) => assert_eq!(err.error, "access_denied"),
+            other => panic!("expected an OAuth error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! The OAuth web application flow, for signing users in with GitHub.
+//!
+//! 1. Redirect the user to [`authorize_url`](struct.OAuthApp.html#method.authorize_url)
+//!    with a random `state`, and remember the state in the user's session.
+//! 2. GitHub redirects back with a `code` and the `state`. Compare the state
+//!    and [`exchange_code`](struct.OAuthApp.html#method.exchange_code) for a token.
+//! 3. Build a client with [`OctokitBuilder::token`](../../struct.OctokitBuilder.html#method.token)
+//!    and, for expiring user-to-server tokens, [`refresh`](struct.OAuthApp.html#method.refresh)
+//!    it before it expires.
+//!
+//! ```no_run
+//! # async fn run(code: &str) -> Result<(), octokit::error::OctokitError> {
+//! use octokit::auth::oauth::OAuthApp;
+//!
+//! let app = OAuthApp::new("client-id", "client-secret");
+//! let state = OAuthApp::random_state();
+//! let redirect_to = app.authorize_url(&["read:user", "repo"], &state, None)?;
+//! // ... once GitHub redirected back to us
+//! let token = app.exchange_code(code, None).await?;
+//! let octokit = octokit::Octokit::new(token.access_token)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use rand::Rng;
+use reqwest::header::ACCEPT;
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::DEFAULT_WEB_URL;
+use crate::client::DEFAULT_USER_AGENT;
+use crate::error::OctokitError;
+use crate::URI;
+
+/// The client id and secret of an OAuth app or GitHub App.
+#[derive(Clone)]
+pub struct OAuthApp {
+    client_id: String,
+    client_secret: String,
+    web_url: URI,
+    http: reqwest::Client,
+}
+
+/// A user access token, as returned by `/login/oauth/access_token`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OAuthToken {
+    pub access_token: String,
+    pub token_type: String,
+    /// Comma separated list of the granted scopes, see [`scopes`](#method.scopes).
+    #[serde(default)]
+    pub scope: String,
+    /// Seconds until the access token expires, only set for expiring
+    /// user-to-server tokens of GitHub Apps.
+    pub expires_in: Option<u64>,
+    pub refresh_token: Option<String>,
+    pub refresh_token_expires_in: Option<u64>,
+}
+
+impl OAuthToken {
+    /// The granted scopes, which may differ from the requested ones.
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.split(',').filter(|scope| !scope.is_empty())
+    }
+}
+
+/// An error response of the OAuth endpoints, which GitHub sends with a `200` status.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OAuthError {
+    /// A machine readable code such as `bad_verification_code` or `incorrect_client_credentials`.
+    pub error: String,
+    pub error_description: Option<String>,
+    pub error_uri: Option<URI>,
+    /// The new polling interval in seconds, sent with `slow_down` during the device flow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+impl fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error_description {
+            Some(description) => write!(f, "{}: {}", self.error, description),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct TokenRequest<'a> {
+    client_id: &'a str,
+    client_secret: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_uri: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grant_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<&'a str>,
+}
+
+/// The endpoints answer with either a token or an error, both with status `200`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum OAuthResponse<T> {
+    Ok(T),
+    Err(OAuthError),
+}
+
+impl OAuthApp {
+    pub fn new(client_id: impl Into<String>, client_secret: impl Into<String>) -> OAuthApp {
+        OAuthApp {
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            web_url: DEFAULT_WEB_URL.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends the OAuth requests to `url` instead of `https://github.com`,
+    /// e.g. a GitHub Enterprise Server instance or a local stub server.
+    pub fn web_url(mut self, url: impl Into<String>) -> Self {
+        self.web_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// An unguessable value for the `state` parameter, which protects the
+    /// callback against cross-site request forgery.
+    pub fn random_state() -> String {
+        hex::encode(rand::thread_rng().gen::<[u8; 20]>())
+    }
+
+    /// The URL to send the user to, asking them to grant `scopes`.
+    ///
+    /// `redirect_uri` defaults to the callback URL configured for the app.
+    pub fn authorize_url(
+        &self,
+        scopes: &[&str],
+        state: &str,
+        redirect_uri: Option<&str>,
+    ) -> Result<URI, OctokitError> {
+        let mut url = Url::parse(&format!("{}/login/oauth/authorize", self.web_url))
+            .map_err(|_| OctokitError::new(&format!("invalid URL: {}", self.web_url)))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("client_id", &self.client_id);
+            if let Some(redirect_uri) = redirect_uri {
+                query.append_pair("redirect_uri", redirect_uri);
+            }
+            if !scopes.is_empty() {
+                query.append_pair("scope", &scopes.join(" "));
+            }
+            query.append_pair("state", state);
+        }
+        Ok(url.into())
+    }
+
+    /// POST /login/oauth/access_token
+    ///
+    /// Exchanges the `code` GitHub redirected back with for a user token.
+    /// `redirect_uri` has to match the one passed to `authorize_url`.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: Option<&str>,
+    ) -> Result<OAuthToken, OctokitError> {
+        self.request_token(&TokenRequest {
+            client_id: &self.client_id,
+            client_secret: &self.client_secret,
+            code: Some(code),
+            redirect_uri,
+            grant_type: None,
+            refresh_token: None,
+        })
+        .await
+    }
+
+    /// POST /login/oauth/access_token
+    ///
+    /// Trades the refresh token of an expiring user-to-server token for a new
+    /// access and refresh token. The old refresh token can not be used again.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<OAuthToken, OctokitError> {
+        self.request_token(&TokenRequest {
+            client_id: &self.client_id,
+            client_secret: &self.client_secret,
+            code: None,
+            redirect_uri: None,
+            grant_type: Some("refresh_token"),
+            refresh_token: Some(refresh_token),
+        })
+        .await
+    }
+
+    async fn request_token(&self, request: &TokenRequest<'_>) -> Result<OAuthToken, OctokitError> {
+        post_form(
+            &self.http,
+            &format!("{}/login/oauth/access_token", self.web_url),
+            request,
+        )
+        .await
+    }
+}
+
+impl fmt::Debug for OAuthApp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print the client secret
+        f.debug_struct("OAuthApp")
+            .field("client_id", &self.client_id)
+            .field("web_url", &self.web_url)
+            .finish()
+    }
+}
+
+/// Posts a form to one of the OAuth endpoints, which report errors in the body.
+pub(crate) async fn post_form<T: DeserializeOwned, F: Serialize>(
+    http: &reqwest::Client,
+    url: &str,
+    form: &F,
+) -> Result<T, OctokitError> {
+    let response = http
+        .post(url)
+        .header(ACCEPT, "application/json")
+        .header(reqwest::header::USER_AGENT, DEFAULT_USER_AGENT)
+        .form(form)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(OctokitError::from_response(response).await);
+    }
+    let body = response.text().await?;
+    match serde_json::from_str(&body) {
+        Ok(OAuthResponse::Ok(token)) => Ok(token),
+        Ok(OAuthResponse::Err(err)) => Err(OctokitError::OAuth(err)),
+        Err(source) => Err(OctokitError::Decode { source, body }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    fn app() -> OAuthApp {
+        OAuthApp::new("Iv1.abc", "shh").web_url(mockito::server_url())
+    }
+
+    #[test]
+    fn builds_authorize_urls() {
+        let url = OAuthApp::new("Iv1.abc", "shh")
+            .authorize_url(
+                &["repo", "read:user"],
+                "xyz",
+                Some("https://example.com/callback"),
+            )
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://github.com/login/oauth/authorize?client_id=Iv1.abc\
+             &redirect_uri=https%3A%2F%2Fexample.com%2Fcallback&scope=repo+read%3Auser&state=xyz"
+        );
+        assert_ne!(OAuthApp::random_state(), OAuthApp::random_state());
+    }
+
+    #[tokio::test]
+    async fn exchanges_codes_for_tokens() {
+        let mock = mockito::mock("POST", "/login/oauth/access_token")
+            .match_header("accept", "application/json")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("client_id".into(), "Iv1.abc".into()),
+                Matcher::UrlEncoded("client_secret".into(), "shh".into()),
+                Matcher::UrlEncoded("code".into(), "good-code".into()),
+            ]))
+            .with_body(
+                r#"{"access_token": "ghu_abc", "expires_in": 28800,
+                    "refresh_token": "ghr_abc", "refresh_token_expires_in": 15811200,
+                    "scope": "repo,read:user", "token_type": "bearer"}"#,
+            )
+            .create();
+
+        let token = app().exchange_code("good-code", None).await.unwrap();
+
+        assert_eq!(token.access_token, "ghu_abc");
+        assert_eq!(token.refresh_token.as_deref(), Some("ghr_abc"));
+        assert_eq!(token.scopes().collect::<Vec<_>>(), ["repo", "read:user"]);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn refreshes_tokens() {
+        let mock = mockito::mock("POST", "/login/oauth/access_token")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("grant_type".into(), "refresh_token".into()),
+                Matcher::UrlEncoded("refresh_token".into(), "ghr_old".into()),
+            ]))
+            .with_body(r#"{"access_token": "ghu_new", "refresh_token": "ghr_new", "token_type": "bearer"}"#)
+            .create();
+
+        let token = app().refresh("ghr_old").await.unwrap();
+
+        assert_eq!(token.access_token, "ghu_new");
+        assert_eq!(token.scope, "");
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn maps_oauth_errors() {
+        let _mock = mockito::mock("POST", "/login/oauth/access_token")
+            .match_body(Matcher::UrlEncoded("code".into(), "stale-code".into()))
+            .with_body(
+                r#"{"error": "bad_verification_code",
+                    "error_description": "The code passed is incorrect or expired.",
+                    "error_uri": "https://docs.github.com/apps/troubleshooting"}"#,
+            )
+            .create();
+
+        match app().exchange_code("stale-code", None).await {
+            Err(OctokitError::OAuth(err)) => assert_eq!(err.error, "bad_verification_code"),
+            other => panic!("expected an OAuth error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! A synchronous API on top of the async [`Octokit`](../struct.Octokit.html) client.
+//!
+//! Every endpoint is a thin wrapper that drives the async implementation on a
+//! runtime owned by the client. The blocking client must not be used from
+//! within an async context; use the async client there instead.
+//!
+//! ```no_run
+//! let octokit = octokit::blocking::Octokit::new("my-token").unwrap();
+//! for pull in octokit.get_pull_requests("octocat/hello-world") {
+//!     println!("{}", pull.unwrap().title);
+//! }
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+use crate::error::OctokitError;
+use crate::hooks::Redelivery;
+use crate::rate_limit::{RateLimit, RateLimitStatus};
+use crate::{
+    CheckRun, CheckSuite, CommitSha, CreateHook, GithubApp, Hook, HookDelivery, Installation,
+    InstallationToken, InstallationTokenRequest, Issue, IssueComment, PullRequest, ReleaseAsset,
+    ReviewComment, UpdateHook, ID,
+};
+
+/// Blocking counterpart of [`crate::Octokit`](../struct.Octokit.html).
+///
+/// Cloning is cheap, clones share the connection pool and the runtime.
+#[derive(Clone, Debug)]
+pub struct Octokit {
+    inner: crate::Octokit,
+    runtime: Arc<Runtime>,
+}
+
+impl Octokit {
+    /// Creates a client that authenticates with an OAuth or installation token.
+    pub fn new(token: impl Into<String>) -> Result<Octokit, OctokitError> {
+        Octokit::from_async(crate::Octokit::new(token)?)
+    }
+
+    /// Wraps an async client, e.g. one configured through [`crate::Octokit::builder`].
+    pub fn from_async(inner: crate::Octokit) -> Result<Octokit, OctokitError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Octokit {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// The most recently reported state of the rate limit bucket `resource`.
+    pub fn last_rate_limit(&self, resource: &str) -> Option<RateLimit> {
+        self.inner.last_rate_limit(resource)
+    }
+
+    /// A client that authenticates as an installation of the app this client
+    /// authenticates as, see [`crate::Octokit::for_installation`](../struct.Octokit.html#method.for_installation).
+    pub fn for_installation(&self, installation_id: ID) -> Result<Octokit, OctokitError> {
+        Ok(Octokit {
+            inner: self.inner.for_installation(installation_id)?,
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    /// The async client this client wraps.
+    pub fn as_async(&self) -> &crate::Octokit {
+        &self.inner
+    }
+}
+
+/// Generates blocking wrappers for async endpoints.
+macro_rules! blocking {
+    ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        impl Octokit {
+            $(
+                #[doc = concat!("Blocking version of [`Octokit::", stringify!($name), "`](../struct.Octokit.html#method.", stringify!($name), ").")]
+                pub fn $name(&self $(, $arg: $ty)*) -> Result<$ret, OctokitError> {
+                    self.runtime.block_on(self.inner.$name($($arg),*))
+                }
+            )*
+        }
+    };
+}
+
+/// Generates blocking wrappers for endpoints that return a paginator.
+macro_rules! blocking_paginated {
+    ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $item:ty;)*) => {
+        impl Octokit {
+            $(
+                #[doc = concat!("Blocking version of [`Octokit::", stringify!($name), "`](../struct.Octokit.html#method.", stringify!($name), ").")]
+                pub fn $name(&self $(, $arg: $ty)*) -> Paginator<$item> {
+                    Paginator {
+                        inner: self.inner.$name($($arg),*),
+                        runtime: self.runtime.clone(),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+blocking! {
+    fn rate_limit(&self) -> RateLimitStatus;
+    fn get_app(&self) -> GithubApp;
+    fn get_installation(&self, installation_id: ID) -> Installation;
+    fn create_installation_token(&self, installation_id: ID, request: &InstallationTokenRequest) -> InstallationToken;
+    fn create_check_suite(&self, nwo: &str, sha: CommitSha) -> CheckSuite;
+    fn create_check_run(&self, nwo: &str, sha: CommitSha) -> CheckRun;
+    fn delete_issue_comment(&self, nwo: &str, comment_number: ID) -> ();
+    fn create_issue_comment(&self, issue_number: ID, repo_name: &str, message: String) -> IssueComment;
+    fn upload_release_asset(&self, nwo: &str, release_id: ID, name: &str, content_type: &str, data: Vec<u8>) -> ReleaseAsset;
+    fn get_repo_hook(&self, nwo: &str, hook_id: ID) -> Hook;
+    fn create_repo_hook(&self, nwo: &str, hook: &CreateHook) -> Hook;
+    fn update_repo_hook(&self, nwo: &str, hook_id: ID, update: &UpdateHook) -> Hook;
+    fn delete_repo_hook(&self, nwo: &str, hook_id: ID) -> ();
+    fn ping_repo_hook(&self, nwo: &str, hook_id: ID) -> ();
+    fn test_repo_hook(&self, nwo: &str, hook_id: ID) -> ();
+    fn get_org_hook(&self, org: &str, hook_id: ID) -> Hook;
+    fn create_org_hook(&self, org: &str, hook: &CreateHook) -> Hook;
+    fn update_org_hook(&self, org: &str, hook_id: ID, update: &UpdateHook) -> Hook;
+    fn delete_org_hook(&self, org: &str, hook_id: ID) -> ();
+    fn ping_org_hook(&self, org: &str, hook_id: ID) -> ();
+    fn get_app_hook_delivery(&self, delivery_id: ID) -> HookDelivery;
+    fn redeliver_app_hook_delivery(&self, delivery_id: ID) -> ();
+    fn redeliver_failed_app_hook_deliveries(&self, since: DateTime<Utc>) -> Vec<Redelivery>;
+    fn get_repo_hook_delivery(&self, nwo: &str, hook_id: ID, delivery_id: ID) -> HookDelivery;
+    fn redeliver_repo_hook_delivery(&self, nwo: &str, hook_id: ID, delivery_id: ID) -> ();
+    fn redeliver_failed_repo_hook_deliveries(&self, nwo: &str, hook_id: ID, since: DateTime<Utc>) -> Vec<Redelivery>;
+}
+
+blocking_paginated! {
+    fn get_all_issues(&self) -> Issue;
+    fn get_pull_requests(&self, nwo: &str) -> PullRequest;
+    fn get_review_comments(&self, nwo: &str, pull_number: ID) -> ReviewComment;
+    fn get_all_review_comments(&self, nwo: &str) -> ReviewComment;
+    fn get_repo_hooks(&self, nwo: &str) -> Hook;
+    fn get_org_hooks(&self, org: &str) -> Hook;
+    fn get_app_hook_deliveries(&self) -> HookDelivery;
+    fn get_repo_hook_deliveries(&self, nwo: &str, hook_id: ID) -> HookDelivery;
+}
+
+/// Blocking counterpart of [`crate::Paginator`](../pagination/struct.Paginator.html),
+/// yielding items across pages as an `Iterator`.
+pub struct Paginator<T> {
+    inner: crate::Paginator<T>,
+    runtime: Arc<Runtime>,
+}
+
+impl<T: DeserializeOwned> Paginator<T> {
+    /// Number of items GitHub returns per page, at most 100.
+    pub fn per_page(mut self, per_page: u8) -> Self {
+        self.inner = self.inner.per_page(per_page);
+        self
+    }
+
+    /// Starts at `cursor` instead of the first page.
+    pub fn cursor(mut self, cursor: &str) -> Self {
+        self.inner = self.inner.cursor(cursor);
+        self
+    }
+
+    /// Stop after `max_pages` pages, even if GitHub announces more.
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.inner = self.inner.max_pages(max_pages);
+        self
+    }
+
+    /// The cursor of the next page, to resume from later with `cursor`.
+    pub fn next_cursor(&self) -> Option<String> {
+        self.inner.next_cursor()
+    }
+
+    /// Number of pages requested so far.
+    pub fn pages_fetched(&self) -> usize {
+        self.inner.pages_fetched()
+    }
+
+    /// URL of the last page, as announced by the most recent response.
+    pub fn last_page_url(&self) -> Option<&str> {
+        self.inner.last_page_url()
+    }
+
+    /// Number of the last page, useful to estimate progress.
+    pub fn last_page(&self) -> Option<u64> {
+        self.inner.last_page()
+    }
+
+    /// Fetches the next page as a whole, skipping any buffered items.
+    pub fn next_page(&mut self) -> Option<Result<Vec<T>, OctokitError>> {
+        self.runtime.block_on(self.inner.next_page())
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for Paginator<T> {
+    type Item = Result<T, OctokitError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.inner.next_item())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drives_async_endpoints_to_completion() {
+        let _mock = mockito::mock("GET", "/repos/octocat/blocking/pulls")
+            .with_header(
+                "link",
+                &format!(
+                    r#"<{}/repos/octocat/blocking/pulls?page=2>; rel="next""#,
+                    mockito::server_url()
+                ),
+            )
+            .with_body("[]")
+            .create();
+        let _second = mockito::mock("GET", "/repos/octocat/blocking/pulls?page=2")
+            .with_body("[]")
+            .create();
+
+        let octokit = Octokit::from_async(crate::testing::mock_client()).unwrap();
+        let mut pulls = octokit.get_pull_requests("octocat/blocking");
+
+        assert!(pulls.next().is_none());
+        assert_eq!(pulls.pages_fetched(), 2);
+    }
+}
@@ -0,0 +1,282 @@
+//! Conditional requests backed by a pluggable response cache.
+//!
+//! GitHub does not count `304 Not Modified` responses against the rate limit.
+//! With a cache configured, every `GET` response that carries an `ETag` or
+//! `Last-Modified` header is stored, and later requests for the same URL are
+//! sent with `If-None-Match`/`If-Modified-Since`. When GitHub answers `304`
+//! the cached body is returned transparently.
+//!
+//! ```no_run
+//! use octokit::cache::InMemoryCache;
+//!
+//! let octokit = octokit::Octokit::builder()
+//!     .token("my-token")
+//!     .cache(InMemoryCache::new(1000))
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use log::warn;
+use reqwest::header::{HeaderMap, HeaderValue, ETAG, LAST_MODIFIED, LINK};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::URI;
+
+/// Identifies a cached response. Responses are never shared between
+/// different credentials, since they may see different data.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub url: URI,
+    /// A digest of the credentials the response was fetched with.
+    pub identity: String,
+}
+
+impl CacheKey {
+    pub(crate) fn new(url: &str, authorization: Option<&str>) -> CacheKey {
+        let identity = match authorization {
+            Some(authorization) => hex::encode(openssl::sha::sha256(authorization.as_bytes())),
+            None => "anonymous".to_string(),
+        };
+        CacheKey {
+            url: url.to_string(),
+            identity,
+        }
+    }
+
+    /// A file system safe digest of the key.
+    fn digest(&self) -> String {
+        hex::encode(openssl::sha::sha256(
+            format!("{}\n{}", self.identity, self.url).as_bytes(),
+        ))
+    }
+}
+
+/// A response body along with the validators needed to revalidate it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Kept so paginated responses can still be followed after a `304`.
+    pub link: Option<String>,
+    #[serde(skip)]
+    pub body: Vec<u8>,
+}
+
+impl CachedResponse {
+    /// Builds a cache entry from response headers, `None` if the response
+    /// carries no validators and therefore cannot be revalidated.
+    pub(crate) fn from_headers(headers: &HeaderMap, body: Vec<u8>) -> Option<CachedResponse> {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(String::from)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        if etag.is_none() && last_modified.is_none() {
+            return None;
+        }
+        Some(CachedResponse {
+            etag,
+            last_modified,
+            link: header(LINK),
+            body,
+        })
+    }
+}
+
+/// Storage for cached responses.
+///
+/// Implementations must be safe to share between threads, since all clones
+/// of a client share the same cache.
+pub trait ResponseCache: Send + Sync {
+    fn get(&self, key: &CacheKey) -> Option<CachedResponse>;
+    fn put(&self, key: CacheKey, response: CachedResponse);
+}
+
+/// An in-memory cache that evicts the least recently used entry once it
+/// holds `capacity` responses.
+pub struct InMemoryCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    tick: u64,
+    entries: HashMap<CacheKey, (u64, CachedResponse)>,
+}
+
+impl InMemoryCache {
+    pub fn new(capacity: usize) -> InMemoryCache {
+        InMemoryCache {
+            capacity: capacity.max(1),
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().map_or(0, |state| state.entries.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ResponseCache for InMemoryCache {
+    fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let mut state = self.state.lock().ok()?;
+        state.tick += 1;
+        let tick = state.tick;
+        let (last_used, response) = state.entries.get_mut(key)?;
+        *last_used = tick;
+        Some(response.clone())
+    }
+
+    fn put(&self, key: CacheKey, response: CachedResponse) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        state.tick += 1;
+        let tick = state.tick;
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.entries.insert(key, (tick, response));
+    }
+}
+
+/// A cache that persists responses in a directory, so they survive restarts.
+///
+/// Every entry is stored as a `.json` file with the validators and a `.body`
+/// file with the raw response body. Entries are never evicted.
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Uses `dir` for the cache, creating it if necessary.
+    pub fn new(dir: impl Into<PathBuf>) -> std::io::Result<DiskCache> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(DiskCache { dir })
+    }
+
+    fn paths(&self, key: &CacheKey) -> (PathBuf, PathBuf) {
+        let digest = key.digest();
+        (
+            self.dir.join(format!("{}.json", digest)),
+            self.dir.join(format!("{}.body", digest)),
+        )
+    }
+}
+
+impl ResponseCache for DiskCache {
+    fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let (meta, body) = self.paths(key);
+        let mut response: CachedResponse = serde_json::from_slice(&fs::read(meta).ok()?).ok()?;
+        response.body = fs::read(body).ok()?;
+        Some(response)
+    }
+
+    fn put(&self, key: CacheKey, response: CachedResponse) {
+        let (meta, body) = self.paths(&key);
+        let result = serde_json::to_vec(&response)
+            .map_err(std::io::Error::from)
+            .and_then(|json| {
+                fs::write(&body, &response.body)?;
+                fs::write(&meta, json)
+            });
+        if let Err(err) = result {
+            warn!("failed to cache response for {}: {}", key.url, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_builder;
+
+    fn response(body: &str) -> CachedResponse {
+        CachedResponse {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            link: None,
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entries() {
+        let cache = InMemoryCache::new(2);
+        let key = |url: &str| CacheKey::new(url, None);
+        cache.put(key("/a"), response("a"));
+        cache.put(key("/b"), response("b"));
+        cache.get(&key("/a"));
+        cache.put(key("/c"), response("c"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key("/a")).is_some());
+        assert!(cache.get(&key("/b")).is_none());
+    }
+
+    #[test]
+    fn separates_credentials() {
+        assert_ne!(
+            CacheKey::new("/a", Some("token one")),
+            CacheKey::new("/a", Some("token two"))
+        );
+    }
+
+    #[test]
+    fn persists_responses_on_disk() {
+        let dir = std::env::temp_dir().join(format!("octokit-cache-{}", std::process::id()));
+        let cache = DiskCache::new(&dir).unwrap();
+        let key = CacheKey::new("/a", Some("token"));
+        cache.put(key.clone(), response("body"));
+
+        let reopened = DiskCache::new(&dir).unwrap();
+        assert_eq!(reopened.get(&key), Some(response("body")));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn revalidates_cached_responses() {
+        let octokit = mock_builder()
+            .token("secret")
+            .cache(InMemoryCache::new(10))
+            .build()
+            .unwrap();
+
+        let fresh = mockito::mock("GET", "/issues")
+            .with_header("etag", "\"v1\"")
+            .with_body(r#"[{"id": 1, "number": 7}]"#)
+            .create();
+        let issue = octokit.get_all_issues().next_item().await.unwrap().unwrap();
+        assert_eq!(issue.number, 7);
+        fresh.assert();
+        drop(fresh);
+
+        let not_modified = mockito::mock("GET", "/issues")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .create();
+        let issue = octokit.get_all_issues().next_item().await.unwrap().unwrap();
+        assert_eq!(issue.number, 7);
+        not_modified.assert();
+    }
+}
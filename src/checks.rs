@@ -0,0 +1,46 @@
+use crate::client::{decode, Octokit};
+use crate::error::OctokitError;
+use crate::{ApiPreviews, CheckRun, CheckSuite, CommitSha, CreateCheckRun, CreateCheckSuite};
+
+impl Octokit {
+    ///  POST /repos/:owner/:repo/check-suites
+    pub async fn create_check_suite(
+        &self,
+        nwo: &str,
+        sha: CommitSha,
+    ) -> Result<CheckSuite, OctokitError> {
+        let data = CreateCheckSuite { head_sha: sha };
+
+        decode(
+            self.post(
+                self.url(&format!("/repos/{}/check-suites", nwo)),
+                &data,
+                ApiPreviews::Antiope,
+            )
+            .await?,
+        )
+        .await
+    }
+
+    ///  POST /repos/:owner/:repo/check-runs
+    pub async fn create_check_run(
+        &self,
+        nwo: &str,
+        sha: CommitSha,
+    ) -> Result<CheckRun, OctokitError> {
+        let data = CreateCheckRun {
+            name: String::from("Example Check-Run"),
+            head_sha: sha,
+        };
+
+        decode(
+            self.post(
+                self.url(&format!("/repos/{}/check-runs", nwo)),
+                &data,
+                ApiPreviews::Antiope,
+            )
+            .await?,
+        )
+        .await
+    }
+}
@@ -0,0 +1,526 @@
+use log::{debug, trace, warn};
+use reqwest::header::{
+    HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LINK, USER_AGENT,
+};
+use reqwest::{Request, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::auth::{AppAuth, Credentials, PersonalAccessToken, StaticJwt};
+use crate::cache::{CacheKey, CachedResponse, ResponseCache};
+use crate::error::OctokitError;
+use crate::rate_limit::{RateLimit, RateLimitPolicy};
+use crate::retry::RetryPolicy;
+use crate::{ApiPreviews, ID, URI};
+
+const DEFAULT_BASE_URL: &str = "https://api.github.com";
+const DEFAULT_UPLOAD_URL: &str = "https://uploads.github.com";
+pub(crate) const DEFAULT_USER_AGENT: &str = "Octokit/Rust v0.1.0";
+/// The rate limit bucket of regular REST API requests.
+const CORE_RESOURCE: &str = "core";
+/// Upper bound of rate limit waits per request, in case GitHub keeps limiting us.
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// A configured, asynchronous GitHub API client.
+///
+/// The client owns a single connection pool, the credentials and the default
+/// headers that are sent with every request. It is cheap to clone and can be
+/// shared across threads and tasks, so build it once and hand out clones.
+///
+/// All endpoints are `async fn`s and need to run on a tokio runtime. See
+/// [`blocking::Octokit`](blocking/struct.Octokit.html) for a synchronous API.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), octokit::error::OctokitError> {
+/// let octokit = octokit::Octokit::new("my-token")?;
+/// let app = octokit.get_app().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Octokit {
+    http: reqwest::Client,
+    credentials: Option<Arc<dyn Credentials>>,
+    base_url: URI,
+    upload_url: URI,
+    rate_limit_policy: RateLimitPolicy,
+    retry_policy: RetryPolicy,
+    cache: Option<Arc<dyn ResponseCache>>,
+    last_rate_limits: Arc<Mutex<HashMap<String, RateLimit>>>,
+}
+
+impl Octokit {
+    /// Creates a client that authenticates with an OAuth or installation token.
+    pub fn new(token: impl Into<String>) -> Result<Octokit, OctokitError> {
+        Octokit::builder().token(token).build()
+    }
+
+    pub fn builder() -> OctokitBuilder {
+        OctokitBuilder::default()
+    }
+
+    /// The URL all API endpoints are resolved against.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The URL release assets are uploaded to.
+    pub fn upload_url(&self) -> &str {
+        &self.upload_url
+    }
+
+    /// The most recently reported state of the rate limit bucket `resource`,
+    /// e.g. `core` or `search`, shared between clones.
+    pub fn last_rate_limit(&self, resource: &str) -> Option<RateLimit> {
+        self.last_rate_limits
+            .lock()
+            .ok()
+            .and_then(|last| last.get(resource).cloned())
+    }
+
+    /// A client that authenticates as an installation of the app this client
+    /// authenticates as.
+    ///
+    /// Installation tokens are created on first use and refreshed before
+    /// they expire. Clients for the same app share their tokens.
+    pub fn for_installation(&self, installation_id: ID) -> Result<Octokit, OctokitError> {
+        self.credentials
+            .as_ref()
+            .and_then(|credentials| credentials.for_installation(installation_id))
+            .map(|credentials| self.with_credentials(credentials))
+            .ok_or_else(|| OctokitError::new("installation clients require GitHub App credentials"))
+    }
+
+    /// A copy of the client that sends different credentials.
+    pub(crate) fn with_credentials(&self, credentials: Arc<dyn Credentials>) -> Octokit {
+        Octokit {
+            credentials: Some(credentials),
+            ..self.clone()
+        }
+    }
+
+    /// Resolves an API path such as `/repos/:owner/:repo` against the base URL.
+    pub(crate) fn url(&self, path: &str) -> URI {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Resolves a path against the upload URL.
+    pub(crate) fn upload(&self, path: &str) -> URI {
+        format!("{}{}", self.upload_url, path)
+    }
+
+    /// Sends a `GET` request, revalidating cached responses if a cache is configured.
+    pub(crate) async fn get(&self, url: URI) -> Result<Response, OctokitError> {
+        let request = self
+            .authorize(self.http.get(&url[..]))
+            .await?
+            .header(ACCEPT, ApiPreviews::Antiope.to_media_type());
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return self.send(request).await,
+        };
+
+        let identity = self
+            .credentials
+            .as_ref()
+            .map(|credentials| credentials.identity());
+        let key = CacheKey::new(&url, identity.as_deref());
+        let cached = cache.get(&key);
+        let request = match &cached {
+            Some(cached) => conditional(request, cached),
+            None => request,
+        };
+        let response = self.send(request).await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                debug!("{} not modified, using cached response", url);
+                let mut headers = response.headers().clone();
+                if let Some(link) = cached.link.as_deref().and_then(|l| l.parse().ok()) {
+                    headers.insert(LINK, link);
+                }
+                return Ok(rebuild(StatusCode::OK, headers, cached.body));
+            }
+            return Ok(response);
+        }
+
+        let (status, headers) = (response.status(), response.headers().clone());
+        let body = response.bytes().await?.to_vec();
+        if let Some(entry) = CachedResponse::from_headers(&headers, body.clone()) {
+            cache.put(key, entry);
+        }
+        Ok(rebuild(status, headers, body))
+    }
+
+    pub(crate) async fn delete(&self, url: URI) -> Result<Response, OctokitError> {
+        self.send(
+            self.authorize(self.http.delete(&url[..]))
+                .await?
+                .header(CONTENT_TYPE, ApiPreviews::Antiope.to_media_type()),
+        )
+        .await
+    }
+
+    pub(crate) async fn post<T: Serialize>(
+        &self,
+        url: URI,
+        data: &T,
+        media_type: ApiPreviews,
+    ) -> Result<Response, OctokitError> {
+        self.send(
+            self.authorize(self.http.post(&url[..]))
+                .await?
+                .header(ACCEPT, media_type.to_media_type())
+                .json(data),
+        )
+        .await
+    }
+
+    /// Sends a `POST` request without a body, e.g. to trigger an action.
+    pub(crate) async fn post_empty(
+        &self,
+        url: URI,
+        media_type: ApiPreviews,
+    ) -> Result<Response, OctokitError> {
+        self.send(
+            self.authorize(self.http.post(&url[..]))
+                .await?
+                .header(ACCEPT, media_type.to_media_type()),
+        )
+        .await
+    }
+
+    pub(crate) async fn patch<T: Serialize>(
+        &self,
+        url: URI,
+        data: &T,
+    ) -> Result<Response, OctokitError> {
+        self.send(
+            self.authorize(self.http.patch(&url[..]))
+                .await?
+                .header(ACCEPT, ApiPreviews::Antiope.to_media_type())
+                .json(data),
+        )
+        .await
+    }
+
+    pub(crate) async fn post_bytes(
+        &self,
+        url: URI,
+        content_type: &str,
+        data: Vec<u8>,
+    ) -> Result<Response, OctokitError> {
+        self.send(
+            self.authorize(self.http.post(&url[..]))
+                .await?
+                .header(CONTENT_TYPE, content_type)
+                .body(data),
+        )
+        .await
+    }
+
+    /// Sends the request and turns every non-success status into an error.
+    ///
+    /// Rate limited requests are retried according to the rate limit policy,
+    /// transient failures according to the retry policy.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, OctokitError> {
+        let request = request.build()?;
+        // rate limit waits do not count as attempts of the retry policy
+        let mut attempt = 0;
+        let mut rate_limit_waits = 0;
+        loop {
+            let pending = match request.try_clone() {
+                Some(pending) => pending,
+                // streaming bodies cannot be replayed, send them exactly once
+                None => return self.execute(request).await,
+            };
+            let err = match self.execute(pending).await {
+                Err(err) => err,
+                ok => return ok,
+            };
+
+            let delay = match err {
+                OctokitError::RateLimited { .. } if rate_limit_waits < MAX_RATE_LIMIT_RETRIES => {
+                    rate_limit_waits += 1;
+                    self.rate_limit_policy.delay_for(&err)
+                }
+                OctokitError::RateLimited { .. } => None,
+                _ => {
+                    attempt += 1;
+                    self.retry_policy.delay_for(request.method(), &err, attempt)
+                }
+            };
+            match delay {
+                Some(delay) => {
+                    warn!("{}, retrying in {}ms", err, delay.as_millis());
+                    tokio::time::sleep(delay).await;
+                }
+                None => return Err(err),
+            }
+        }
+    }
+
+    async fn execute(&self, request: Request) -> Result<Response, OctokitError> {
+        let (method, url) = (request.method().clone(), request.url().clone());
+        trace!("{} {}", method, url);
+
+        let response = self.http.execute(request).await.map_err(|err| {
+            debug!("{} {} failed: {}", method, url, err);
+            OctokitError::from(err)
+        })?;
+        debug!("{} {} -> {}", method, url, response.status());
+
+        if let Some(rate_limit) = RateLimit::from_headers(response.headers()) {
+            // responses without a resource header are counted against `core`
+            let resource = rate_limit
+                .resource
+                .clone()
+                .unwrap_or_else(|| CORE_RESOURCE.to_string());
+            if let Ok(mut last) = self.last_rate_limits.lock() {
+                last.insert(resource, rate_limit);
+            }
+        }
+
+        // 304s are only ever provoked by the response cache, which handles them
+        if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+            Ok(response)
+        } else {
+            Err(OctokitError::from_response(response).await)
+        }
+    }
+
+    async fn authorize(&self, request: RequestBuilder) -> Result<RequestBuilder, OctokitError> {
+        Ok(match &self.credentials {
+            Some(credentials) => {
+                request.header(AUTHORIZATION, credentials.authorization(self).await?)
+            }
+            None => request,
+        })
+    }
+}
+
+/// Adds the validators of a cached response, so GitHub can answer with `304`.
+fn conditional(request: RequestBuilder, cached: &CachedResponse) -> RequestBuilder {
+    let request = match &cached.etag {
+        Some(etag) => request.header(IF_NONE_MATCH, etag.as_str()),
+        None => request,
+    };
+    match &cached.last_modified {
+        Some(last_modified) => request.header(IF_MODIFIED_SINCE, last_modified.as_str()),
+        None => request,
+    }
+}
+
+/// Builds a response from an already consumed or cached body.
+fn rebuild(status: StatusCode, headers: HeaderMap, body: Vec<u8>) -> Response {
+    let mut response = http::Response::new(body);
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    Response::from(response)
+}
+
+impl fmt::Debug for Octokit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print the credentials themselves
+        f.debug_struct("Octokit")
+            .field("base_url", &self.base_url)
+            .field("upload_url", &self.upload_url)
+            .field("rate_limit_policy", &self.rate_limit_policy)
+            .field("retry_policy", &self.retry_policy)
+            .field("authenticated", &self.credentials.is_some())
+            .field("cached", &self.cache.is_some())
+            .finish()
+    }
+}
+
+/// Builder for [`Octokit`](struct.Octokit.html) clients.
+#[derive(Default)]
+pub struct OctokitBuilder {
+    credentials: Option<Arc<dyn Credentials>>,
+    base_url: Option<URI>,
+    upload_url: Option<URI>,
+    user_agent: Option<String>,
+    headers: HeaderMap,
+    rate_limit_policy: RateLimitPolicy,
+    retry_policy: RetryPolicy,
+    cache: Option<Arc<dyn ResponseCache>>,
+}
+
+impl OctokitBuilder {
+    /// Authenticate with an OAuth, personal access or installation token.
+    pub fn token(self, token: impl Into<String>) -> Self {
+        self.credentials(PersonalAccessToken(token.into()))
+    }
+
+    /// Authenticate as a GitHub App with a JWT, see [`create_jwt`](fn.create_jwt.html).
+    pub fn jwt(self, jwt: impl Into<String>) -> Self {
+        self.credentials(StaticJwt(jwt.into()))
+    }
+
+    /// Authenticate as a GitHub App, minting a fresh JWT whenever one is
+    /// needed. See [`Octokit::for_installation`](struct.Octokit.html#method.for_installation)
+    /// to act on behalf of an installation.
+    pub fn app(self, app: AppAuth) -> Self {
+        self.credentials(app)
+    }
+
+    /// Authenticate with any [`Credentials`](auth/trait.Credentials.html),
+    /// e.g. [`BasicAuth`](auth/struct.BasicAuth.html) or a custom implementation.
+    pub fn credentials(mut self, credentials: impl Credentials + 'static) -> Self {
+        self.credentials = Some(Arc::new(credentials));
+        self
+    }
+
+    /// Resolve all API endpoints against `url` instead of `https://api.github.com`.
+    ///
+    /// This is mostly useful to point tests at a local mock server. For GitHub
+    /// Enterprise Server see [`enterprise`](#method.enterprise).
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Upload release assets to `url` instead of `https://uploads.github.com`.
+    pub fn upload_url(mut self, url: impl Into<String>) -> Self {
+        self.upload_url = Some(url.into());
+        self
+    }
+
+    /// Configures the client for a GitHub Enterprise Server instance.
+    ///
+    /// `host` is the root of the instance, e.g. `https://ghe.example.com`. The
+    /// API is then served from `/api/v3` and uploads from `/api/uploads`.
+    pub fn enterprise(self, host: &str) -> Self {
+        let host = host.trim_end_matches('/');
+        self.base_url(format!("{}/api/v3", host))
+            .upload_url(format!("{}/api/uploads", host))
+    }
+
+    /// Whether to wait for exhausted rate limits instead of failing, see
+    /// [`RateLimitPolicy`](rate_limit/enum.RateLimitPolicy.html).
+    pub fn rate_limit_policy(mut self, policy: RateLimitPolicy) -> Self {
+        self.rate_limit_policy = policy;
+        self
+    }
+
+    /// How transient failures are retried, see
+    /// [`RetryPolicy`](retry/struct.RetryPolicy.html). Idempotent requests are
+    /// retried up to three times by default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Revalidate `GET` responses with conditional requests, see the
+    /// [`cache`](cache/index.html) module. Clones of the client share the cache.
+    pub fn cache(mut self, cache: impl ResponseCache + 'static) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Adds a header that is sent with every request.
+    pub fn default_header(mut self, name: &'static str, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn build(self) -> Result<Octokit, OctokitError> {
+        let mut headers = self.headers;
+        let user_agent = self
+            .user_agent
+            .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_str(&user_agent)
+                .map_err(|_| OctokitError::new("invalid user agent"))?,
+        );
+
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?;
+
+        Ok(Octokit {
+            http,
+            credentials: self.credentials,
+            base_url: parse_url(self.base_url, DEFAULT_BASE_URL)?,
+            upload_url: parse_url(self.upload_url, DEFAULT_UPLOAD_URL)?,
+            rate_limit_policy: self.rate_limit_policy,
+            retry_policy: self.retry_policy,
+            cache: self.cache,
+            last_rate_limits: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+}
+
+/// Decodes a JSON response body, keeping the body around for the error if it does not match `T`.
+pub(crate) async fn decode<T: DeserializeOwned>(response: Response) -> Result<T, OctokitError> {
+    let body = response.text().await?;
+    serde_json::from_str(&body).map_err(|source| OctokitError::Decode { source, body })
+}
+
+/// Validates a configured URL and strips trailing slashes, so paths can be appended as-is.
+fn parse_url(url: Option<URI>, default: &str) -> Result<URI, OctokitError> {
+    let url = url.unwrap_or_else(|| default.to_string());
+    reqwest::Url::parse(&url).map_err(|_| OctokitError::new(&format!("invalid URL: {}", url)))?;
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_paths_against_github_by_default() {
+        let octokit = Octokit::builder().build().unwrap();
+        assert_eq!(octokit.url("/app"), "https://api.github.com/app");
+        assert_eq!(
+            octokit.upload("/repos/o/r/releases/1/assets"),
+            "https://uploads.github.com/repos/o/r/releases/1/assets"
+        );
+    }
+
+    #[test]
+    fn resolves_paths_against_enterprise_server() {
+        let octokit = Octokit::builder()
+            .enterprise("https://ghe.example.com/")
+            .build()
+            .unwrap();
+        assert_eq!(octokit.url("/app"), "https://ghe.example.com/api/v3/app");
+        assert_eq!(
+            octokit.upload("/repos/o/r/releases/1/assets"),
+            "https://ghe.example.com/api/uploads/repos/o/r/releases/1/assets"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_base_url() {
+        assert!(Octokit::builder().base_url("not a url").build().is_err());
+    }
+
+    #[tokio::test]
+    async fn sends_requests_to_configured_base_url() {
+        let mock = mockito::mock("POST", "/repos/octocat/hello-world/issues/1/comments")
+            .match_header("authorization", "token secret")
+            .with_status(201)
+            .with_body(r#"{"id": 1, "body": "hi", "user": {"id": 2, "login": "octocat", "node_id": "MDQ", "type": "User", "site_admin": false}}"#)
+            .create();
+
+        let octokit = crate::testing::mock_client();
+        let comment = octokit
+            .create_issue_comment(1, "octocat/hello-world", "hi".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(comment.body, "hi");
+        mock.assert();
+    }
+}
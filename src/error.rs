@@ -1,51 +1,333 @@
-use std::error::Error;
+use reqwest::{Response, StatusCode};
 use std::fmt;
+use std::time::Duration;
 
+use crate::auth::oauth::OAuthError;
+use crate::rate_limit::{self, RateLimit};
+use crate::webhooks::SignatureError;
+use crate::ApiError;
+
+/// Everything that can go wrong while talking to GitHub.
+///
+/// Errors returned by GitHub itself carry the status code and the decoded
+/// [`ApiError`](../struct.ApiError.html) body, so callers can tell a missing
+/// resource (404) from a validation failure (422) or missing permissions (403).
 #[derive(Debug)]
-pub struct OctokitError {
-    details: String,
+pub enum OctokitError {
+    /// The request did not produce a response, e.g. DNS, TLS or connection failures.
+    Transport(reqwest::Error),
+    /// GitHub answered with a non-success status.
+    Http {
+        status: StatusCode,
+        error: ApiError,
+    },
+    /// The credentials were missing, invalid or expired (401).
+    Unauthorized(ApiError),
+    /// A primary or secondary rate limit was hit, answered with `403` or `429`.
+    ///
+    /// Primary limits carry the exhausted `rate_limit`, secondary limits
+    /// usually tell how long to back off through `retry_after`.
+    RateLimited {
+        status: StatusCode,
+        rate_limit: Option<Box<RateLimit>>,
+        retry_after: Option<Duration>,
+        error: ApiError,
+    },
+    /// A response body did not match the expected shape.
+    Decode {
+        source: serde_json::Error,
+        body: String,
+    },
+    Json(serde_json::Error),
+    Io(std::io::Error),
+    Hex(hex::FromHexError),
+    Crypto(openssl::error::ErrorStack),
+    /// An OAuth endpoint rejected the request, e.g. an expired code.
+    OAuth(OAuthError),
+    /// A webhook delivery failed signature verification.
+    Signature(SignatureError),
+    /// A JWT could not be signed.
+    Jwt(jsonwebtoken::errors::Error),
+    /// Invalid client configuration or arguments.
+    Config(String),
 }
 
 impl OctokitError {
-    fn new(msg: &str) -> OctokitError {
-        OctokitError {
-            details: msg.to_string(),
+    pub(crate) fn new(msg: &str) -> OctokitError {
+        OctokitError::Config(msg.to_string())
+    }
+
+    /// Turns a non-success response into the matching error variant.
+    pub(crate) async fn from_response(response: Response) -> OctokitError {
+        let status = response.status();
+        let rate_limit = RateLimit::from_headers(response.headers()).map(Box::new);
+        let retry_after = rate_limit::retry_after(response.headers());
+
+        let body = response.text().await.unwrap_or_default();
+        let error = serde_json::from_str::<ApiError>(&body).unwrap_or_else(|_| ApiError {
+            message: if body.is_empty() {
+                status.canonical_reason().unwrap_or_default().to_string()
+            } else {
+                body
+            },
+            documentation_url: None,
+            errors: Vec::new(),
+        });
+
+        let exhausted = rate_limit
+            .as_ref()
+            .is_some_and(|limit| limit.remaining == 0);
+        let secondary = retry_after.is_some() || error.message.contains("secondary rate limit");
+        match status {
+            StatusCode::UNAUTHORIZED => OctokitError::Unauthorized(error),
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS if exhausted || secondary => {
+                OctokitError::RateLimited {
+                    status,
+                    rate_limit,
+                    retry_after,
+                    error,
+                }
+            }
+            _ => OctokitError::Http { status, error },
+        }
+    }
+
+    /// The HTTP status GitHub answered with, if the error originates from a response.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            OctokitError::Http { status, .. } | OctokitError::RateLimited { status, .. } => {
+                Some(*status)
+            }
+            OctokitError::Unauthorized(_) => Some(StatusCode::UNAUTHORIZED),
+            OctokitError::Transport(err) => err.status(),
+            _ => None,
+        }
+    }
+
+    /// The error body GitHub answered with, if any.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            OctokitError::Http { error, .. }
+            | OctokitError::Unauthorized(error)
+            | OctokitError::RateLimited { error, .. } => Some(error),
+            _ => None,
         }
     }
+
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
 }
 
 impl fmt::Display for OctokitError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.details)
+        match self {
+            OctokitError::Transport(err) => write!(f, "request failed: {}", err),
+            OctokitError::Http { status, error } => {
+                write!(f, "GitHub responded with {}: {}", status, error.message)
+            }
+            OctokitError::Unauthorized(error) => write!(f, "unauthorized: {}", error.message),
+            OctokitError::RateLimited {
+                rate_limit,
+                retry_after,
+                ..
+            } => match (rate_limit, retry_after) {
+                (_, Some(retry_after)) => write!(
+                    f,
+                    "secondary rate limit exceeded, retry after {}s",
+                    retry_after.as_secs()
+                ),
+                (Some(rate_limit), None) => {
+                    write!(f, "rate limit exceeded, resets at {}", rate_limit.reset)
+                }
+                (None, None) => write!(f, "rate limit exceeded"),
+            },
+            OctokitError::Decode { source, .. } => {
+                write!(f, "failed to decode response body: {}", source)
+            }
+            OctokitError::Json(err) => write!(f, "{}", err),
+            OctokitError::Io(err) => write!(f, "{}", err),
+            OctokitError::Hex(err) => write!(f, "{}", err),
+            OctokitError::Crypto(err) => write!(f, "{}", err),
+            OctokitError::OAuth(err) => write!(f, "OAuth request failed: {}", err),
+            OctokitError::Signature(err) => write!(f, "{}", err),
+            OctokitError::Jwt(err) => write!(f, "failed to create JWT: {}", err),
+            OctokitError::Config(details) => write!(f, "{}", details),
+        }
     }
 }
 
 impl std::error::Error for OctokitError {
-    fn description(&self) -> &str {
-        &self.details
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OctokitError::Transport(err) => Some(err),
+            OctokitError::Decode { source, .. } => Some(source),
+            OctokitError::Json(err) => Some(err),
+            OctokitError::Io(err) => Some(err),
+            OctokitError::Hex(err) => Some(err),
+            OctokitError::Crypto(err) => Some(err),
+            OctokitError::Jwt(err) => Some(err),
+            _ => None,
+        }
     }
 }
 
 impl From<serde_json::Error> for OctokitError {
     fn from(err: serde_json::Error) -> Self {
-        OctokitError::new(err.description())
+        OctokitError::Json(err)
+    }
+}
+
+impl From<std::io::Error> for OctokitError {
+    fn from(err: std::io::Error) -> Self {
+        OctokitError::Io(err)
     }
 }
 
 impl From<reqwest::Error> for OctokitError {
     fn from(err: reqwest::Error) -> Self {
-        OctokitError::new(err.description())
+        OctokitError::Transport(err)
     }
 }
 
 impl From<hex::FromHexError> for OctokitError {
     fn from(err: hex::FromHexError) -> Self {
-        OctokitError::new(err.description())
+        OctokitError::Hex(err)
     }
 }
 
 impl From<openssl::error::ErrorStack> for OctokitError {
     fn from(err: openssl::error::ErrorStack) -> Self {
-        OctokitError::new(err.description())
+        OctokitError::Crypto(err)
+    }
+}
+
+impl From<SignatureError> for OctokitError {
+    fn from(err: SignatureError) -> Self {
+        OctokitError::Signature(err)
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for OctokitError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        OctokitError::Jwt(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_client;
+
+    #[tokio::test]
+    async fn maps_validation_failures() {
+        let _mock = mockito::mock("POST", "/repos/octocat/errors/check-suites")
+            .with_status(422)
+            .with_body(
+                r#"{"message": "Validation Failed",
+                    "errors": [{"resource": "CheckSuite", "field": "head_sha", "code": "missing_field"}],
+                    "documentation_url": "https://developer.github.com/v3/checks/suites/"}"#,
+            )
+            .create();
+
+        let err = mock_client()
+            .create_check_suite("octocat/errors", "abc".to_string())
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status(), Some(StatusCode::UNPROCESSABLE_ENTITY));
+        let api_error = err.api_error().unwrap();
+        assert_eq!(api_error.message, "Validation Failed");
+        assert_eq!(api_error.errors[0].field.as_deref(), Some("head_sha"));
+        assert!(api_error.documentation_url.is_some());
+    }
+
+    #[tokio::test]
+    async fn maps_bad_credentials() {
+        let _mock = mockito::mock("GET", "/repos/octocat/unauthorized/pulls")
+            .with_status(401)
+            .with_body(r#"{"message": "Bad credentials"}"#)
+            .create();
+
+        let err = mock_client()
+            .get_pull_requests("octocat/unauthorized")
+            .next_item()
+            .await
+            .unwrap()
+            .unwrap_err();
+        match err {
+            OctokitError::Unauthorized(error) => assert_eq!(error.message, "Bad credentials"),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn maps_exhausted_rate_limit() {
+        let _mock = mockito::mock("GET", "/repos/octocat/limited/pulls")
+            .with_status(403)
+            .with_header("x-ratelimit-limit", "5000")
+            .with_header("x-ratelimit-remaining", "0")
+            .with_header("x-ratelimit-reset", "1372700873")
+            .with_body(r#"{"message": "API rate limit exceeded"}"#)
+            .create();
+
+        let err = mock_client()
+            .get_pull_requests("octocat/limited")
+            .next_item()
+            .await
+            .unwrap()
+            .unwrap_err();
+        match err {
+            OctokitError::RateLimited { rate_limit, .. } => {
+                assert_eq!(rate_limit.unwrap().reset, 1_372_700_873)
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_status_of_secondary_rate_limits() {
+        let _mock = mockito::mock("GET", "/repos/octocat/too-many/pulls")
+            .with_status(429)
+            .with_header("retry-after", "30")
+            .with_body(r#"{"message": "You have exceeded a secondary rate limit."}"#)
+            .create();
+
+        let err = mock_client()
+            .get_pull_requests("octocat/too-many")
+            .next_item()
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(err, OctokitError::RateLimited { .. }));
+        assert_eq!(err.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+    }
+
+    #[tokio::test]
+    async fn maps_empty_server_errors() {
+        let _mock = mockito::mock("GET", "/app").with_status(502).create();
+
+        let err = mock_client().get_app().await.unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::BAD_GATEWAY));
+        assert_eq!(err.api_error().unwrap().message, "Bad Gateway");
+    }
+
+    #[tokio::test]
+    async fn keeps_body_of_undecodable_responses() {
+        let _mock = mockito::mock("GET", "/repos/octocat/garbage/pulls")
+            .with_status(200)
+            .with_body("not json")
+            .create();
+
+        match mock_client()
+            .get_pull_requests("octocat/garbage")
+            .next_item()
+            .await
+            .unwrap()
+            .unwrap_err()
+        {
+            OctokitError::Decode { body, .. } => assert_eq!(body, "not json"),
+            other => panic!("unexpected error {:?}", other),
+        }
     }
 }
@@ -0,0 +1,466 @@
+use chrono::{DateTime, Utc};
+use log::debug;
+use std::collections::HashSet;
+
+use crate::client::{decode, Octokit};
+use crate::error::OctokitError;
+use crate::pagination::Paginator;
+use crate::{ApiPreviews, CreateHook, Hook, HookDelivery, UpdateHook, ID};
+
+/// A failed delivery and whether asking GitHub to redeliver it worked.
+#[derive(Debug)]
+pub struct Redelivery {
+    /// The latest failed attempt of the delivery.
+    pub delivery: HookDelivery,
+    pub result: Result<(), OctokitError>,
+}
+
+impl Octokit {
+    /// GET /repos/:owner/:repo/hooks
+    pub fn get_repo_hooks(&self, nwo: &str) -> Paginator<Hook> {
+        Paginator::new(self, self.url(&format!("/repos/{}/hooks", nwo)))
+    }
+
+    /// GET /repos/:owner/:repo/hooks/:hook_id
+    pub async fn get_repo_hook(&self, nwo: &str, hook_id: ID) -> Result<Hook, OctokitError> {
+        let url = self.url(&format!("/repos/{}/hooks/{}", nwo, hook_id));
+        decode(self.get(url).await?).await
+    }
+
+    /// POST /repos/:owner/:repo/hooks
+    pub async fn create_repo_hook(
+        &self,
+        nwo: &str,
+        hook: &CreateHook,
+    ) -> Result<Hook, OctokitError> {
+        let url = self.url(&format!("/repos/{}/hooks", nwo));
+        decode(self.post(url, hook, ApiPreviews::Antiope).await?).await
+    }
+
+    /// PATCH /repos/:owner/:repo/hooks/:hook_id
+    pub async fn update_repo_hook(
+        &self,
+        nwo: &str,
+        hook_id: ID,
+        update: &UpdateHook,
+    ) -> Result<Hook, OctokitError> {
+        let url = self.url(&format!("/repos/{}/hooks/{}", nwo, hook_id));
+        decode(self.patch(url, update).await?).await
+    }
+
+    /// DELETE /repos/:owner/:repo/hooks/:hook_id
+    pub async fn delete_repo_hook(&self, nwo: &str, hook_id: ID) -> Result<(), OctokitError> {
+        let url = self.url(&format!("/repos/{}/hooks/{}", nwo, hook_id));
+        self.delete(url).await?;
+        Ok(())
+    }
+
+    /// POST /repos/:owner/:repo/hooks/:hook_id/pings
+    ///
+    /// Sends a `ping` event to the hook.
+    pub async fn ping_repo_hook(&self, nwo: &str, hook_id: ID) -> Result<(), OctokitError> {
+        let url = self.url(&format!("/repos/{}/hooks/{}/pings", nwo, hook_id));
+        self.post_empty(url, ApiPreviews::Antiope).await?;
+        Ok(())
+    }
+
+    /// POST /repos/:owner/:repo/hooks/:hook_id/tests
+    ///
+    /// Sends the latest push to the hook again, if it subscribes to `push`.
+    pub async fn test_repo_hook(&self, nwo: &str, hook_id: ID) -> Result<(), OctokitError> {
+        let url = self.url(&format!("/repos/{}/hooks/{}/tests", nwo, hook_id));
+        self.post_empty(url, ApiPreviews::Antiope).await?;
+        Ok(())
+    }
+
+    /// GET /orgs/:org/hooks
+    pub fn get_org_hooks(&self, org: &str) -> Paginator<Hook> {
+        Paginator::new(self, self.url(&format!("/orgs/{}/hooks", org)))
+    }
+
+    /// GET /orgs/:org/hooks/:hook_id
+    pub async fn get_org_hook(&self, org: &str, hook_id: ID) -> Result<Hook, OctokitError> {
+        let url = self.url(&format!("/orgs/{}/hooks/{}", org, hook_id));
+        decode(self.get(url).await?).await
+    }
+
+    /// POST /orgs/:org/hooks
+    pub async fn create_org_hook(
+        &self,
+        org: &str,
+        hook: &CreateHook,
+    ) -> Result<Hook, OctokitError> {
+        let url = self.url(&format!("/orgs/{}/hooks", org));
+        decode(self.post(url, hook, ApiPreviews::Antiope).await?).await
+    }
+
+    /// PATCH /orgs/:org/hooks/:hook_id
+    ///
+    /// `add_events` and `remove_events` are not supported for organization hooks.
+    pub async fn update_org_hook(
+        &self,
+        org: &str,
+        hook_id: ID,
+        update: &UpdateHook,
+    ) -> Result<Hook, OctokitError> {
+        let url = self.url(&format!("/orgs/{}/hooks/{}", org, hook_id));
+        decode(self.patch(url, update).await?).await
+    }
+
+    /// DELETE /orgs/:org/hooks/:hook_id
+    pub async fn delete_org_hook(&self, org: &str, hook_id: ID) -> Result<(), OctokitError> {
+        let url = self.url(&format!("/orgs/{}/hooks/{}", org, hook_id));
+        self.delete(url).await?;
+        Ok(())
+    }
+
+    /// POST /orgs/:org/hooks/:hook_id/pings
+    ///
+    /// Sends a `ping` event to the hook. Organization hooks can not be tested
+    /// with a push like repository hooks.
+    pub async fn ping_org_hook(&self, org: &str, hook_id: ID) -> Result<(), OctokitError> {
+        let url = self.url(&format!("/orgs/{}/hooks/{}/pings", org, hook_id));
+        self.post_empty(url, ApiPreviews::Antiope).await?;
+        Ok(())
+    }
+
+    /// GET /app/hook/deliveries
+    ///
+    /// The deliveries of the app webhook, newest first. Requires JWT
+    /// authentication. Pages are linked by cursor, see
+    /// [`Paginator::cursor`](pagination/struct.Paginator.html#method.cursor).
+    pub fn get_app_hook_deliveries(&self) -> Paginator<HookDelivery> {
+        Paginator::new(self, self.url("/app/hook/deliveries"))
+    }
+
+    /// GET /app/hook/deliveries/:delivery_id
+    pub async fn get_app_hook_delivery(
+        &self,
+        delivery_id: ID,
+    ) -> Result<HookDelivery, OctokitError> {
+        let url = self.url(&format!("/app/hook/deliveries/{}", delivery_id));
+        decode(self.get(url).await?).await
+    }
+
+    /// POST /app/hook/deliveries/:delivery_id/attempts
+    pub async fn redeliver_app_hook_delivery(&self, delivery_id: ID) -> Result<(), OctokitError> {
+        let url = self.url(&format!("/app/hook/deliveries/{}/attempts", delivery_id));
+        self.post_empty(url, ApiPreviews::MachineMan).await?;
+        Ok(())
+    }
+
+    /// GET /repos/:owner/:repo/hooks/:hook_id/deliveries
+    ///
+    /// The deliveries of a repository webhook, newest first.
+    pub fn get_repo_hook_deliveries(&self, nwo: &str, hook_id: ID) -> Paginator<HookDelivery> {
+        let url = self.url(&format!("/repos/{}/hooks/{}/deliveries", nwo, hook_id));
+        Paginator::new(self, url)
+    }
+
+    /// GET /repos/:owner/:repo/hooks/:hook_id/deliveries/:delivery_id
+    pub async fn get_repo_hook_delivery(
+        &self,
+        nwo: &str,
+        hook_id: ID,
+        delivery_id: ID,
+    ) -> Result<HookDelivery, OctokitError> {
+        let url = self.url(&format!(
+            "/repos/{}/hooks/{}/deliveries/{}",
+            nwo, hook_id, delivery_id
+        ));
+        decode(self.get(url).await?).await
+    }
+
+    /// POST /repos/:owner/:repo/hooks/:hook_id/deliveries/:delivery_id/attempts
+    pub async fn redeliver_repo_hook_delivery(
+        &self,
+        nwo: &str,
+        hook_id: ID,
+        delivery_id: ID,
+    ) -> Result<(), OctokitError> {
+        let url = self.url(&format!(
+            "/repos/{}/hooks/{}/deliveries/{}/attempts",
+            nwo, hook_id, delivery_id
+        ));
+        self.post_empty(url, ApiPreviews::Antiope).await?;
+        Ok(())
+    }
+
+    /// Redelivers the app webhook deliveries since `since` that never
+    /// succeeded, e.g. after the receiver was down.
+    ///
+    /// A failed redelivery does not stop the others, check the `result` of
+    /// every returned [`Redelivery`](hooks/struct.Redelivery.html).
+    pub async fn redeliver_failed_app_hook_deliveries(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Redelivery>, OctokitError> {
+        let mut redeliveries = Vec::new();
+        for delivery in failed_deliveries(self.get_app_hook_deliveries(), since).await? {
+            let result = self.redeliver_app_hook_delivery(delivery.id).await;
+            redeliveries.push(Redelivery { delivery, result });
+        }
+        Ok(redeliveries)
+    }
+
+    /// Like [`redeliver_failed_app_hook_deliveries`](#method.redeliver_failed_app_hook_deliveries)
+    /// for a repository webhook.
+    pub async fn redeliver_failed_repo_hook_deliveries(
+        &self,
+        nwo: &str,
+        hook_id: ID,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Redelivery>, OctokitError> {
+        let failed = failed_deliveries(self.get_repo_hook_deliveries(nwo, hook_id), since).await?;
+        let mut redeliveries = Vec::new();
+        for delivery in failed {
+            let result = self
+                .redeliver_repo_hook_delivery(nwo, hook_id, delivery.id)
+                .await;
+            redeliveries.push(Redelivery { delivery, result });
+        }
+        Ok(redeliveries)
+    }
+}
+
+/// The latest attempt of every delivery since `since` without a successful attempt.
+async fn failed_deliveries(
+    deliveries: Paginator<HookDelivery>,
+    since: DateTime<Utc>,
+) -> Result<Vec<HookDelivery>, OctokitError> {
+    let mut deliveries = deliveries.per_page(100);
+    let mut succeeded = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut failed = Vec::new();
+    while let Some(delivery) = deliveries.next_item().await {
+        let delivery = delivery?;
+        // deliveries are listed newest first
+        if delivery.delivered_at < since {
+            break;
+        }
+        if delivery.succeeded() {
+            succeeded.insert(delivery.guid.clone());
+        } else if seen.insert(delivery.guid.clone()) {
+            failed.push(delivery);
+        }
+    }
+    failed.retain(|delivery| !succeeded.contains(&delivery.guid));
+    debug!("found {} failed deliveries", failed.len());
+    Ok(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HookConfig, HookContentType};
+    use mockito::Matcher;
+
+    fn delivery(id: ID, guid: &str, delivered_at: &str, status_code: u16) -> String {
+        format!(
+            r#"{{"id": {}, "guid": "{}", "delivered_at": "{}", "redelivery": false,
+                "duration": 0.27, "status": "OK", "status_code": {}, "event": "issues",
+                "action": "opened", "installation_id": 123, "repository_id": 456}}"#,
+            id, guid, delivered_at, status_code
+        )
+    }
+
+    #[tokio::test]
+    async fn follows_delivery_cursors() {
+        let next = format!(
+            "<{}/app/hook/deliveries?per_page=1&cursor=v1_123>; rel=\"next\"",
+            mockito::server_url()
+        );
+        let _first = mockito::mock("GET", "/app/hook/deliveries")
+            .match_query(Matcher::UrlEncoded("per_page".into(), "1".into()))
+            .with_header("link", &next)
+            .with_body(format!(
+                "[{}]",
+                delivery(2, "a", "2024-05-02T10:00:00Z", 200)
+            ))
+            .create();
+
+        let mut deliveries = crate::testing::mock_client()
+            .get_app_hook_deliveries()
+            .per_page(1);
+        let page = deliveries.next_page().await.unwrap().unwrap();
+
+        assert_eq!(page[0].guid, "a");
+        assert_eq!(deliveries.next_cursor().as_deref(), Some("v1_123"));
+    }
+
+    #[tokio::test]
+    async fn fetches_deliveries_with_request_and_response() {
+        let _mock = mockito::mock("GET", "/repos/octocat/hooks/hooks/1/deliveries/12345")
+            .with_body(
+                r#"{"id": 12345, "guid": "0b989ba4-242f-11e5-81e1-c7b6966d2516",
+                    "delivered_at": "2019-06-03T00:57:16Z", "redelivery": false,
+                    "duration": 0.27, "status": "Invalid HTTP Response: 503", "status_code": 503,
+                    "event": "issues", "action": "opened", "installation_id": null,
+                    "repository_id": 456, "url": "https://www.example.com",
+                    "request": {"headers": {"X-GitHub-Event": "issues"}, "payload": {"action": "opened"}},
+                    "response": {"headers": {"Content-Type": "text/html"}, "payload": "down"}}"#,
+            )
+            .create();
+
+        let delivery = crate::testing::mock_client()
+            .get_repo_hook_delivery("octocat/hooks", 1, 12345)
+            .await
+            .unwrap();
+
+        assert!(!delivery.succeeded());
+        assert_eq!(
+            delivery.request.unwrap().payload.unwrap()["action"],
+            "opened"
+        );
+        assert_eq!(delivery.response.unwrap().payload.as_deref(), Some("down"));
+    }
+
+    #[tokio::test]
+    async fn redelivers_failed_deliveries_since() {
+        let _list = mockito::mock("GET", "/repos/octocat/redeliver/hooks/1/deliveries")
+            .match_query(Matcher::Any)
+            .with_body(format!(
+                "[{}, {}, {}, {}, {}, {}]",
+                // "a" failed twice, only the latest attempt is redelivered
+                delivery(6, "a", "2024-05-02T12:00:00Z", 502),
+                // "b" succeeded on redelivery
+                delivery(5, "b", "2024-05-02T11:00:00Z", 200),
+                delivery(4, "a", "2024-05-02T10:00:00Z", 0),
+                delivery(3, "b", "2024-05-02T09:00:00Z", 500),
+                delivery(2, "d", "2024-05-02T08:00:00Z", 500),
+                // before `since`
+                delivery(1, "c", "2024-05-01T09:00:00Z", 500),
+            ))
+            .create();
+        let rejected = mockito::mock(
+            "POST",
+            "/repos/octocat/redeliver/hooks/1/deliveries/6/attempts",
+        )
+        .with_status(422)
+        .with_body(r#"{"message": "Validation Failed"}"#)
+        .expect(1)
+        .create();
+        let redeliver = mockito::mock(
+            "POST",
+            "/repos/octocat/redeliver/hooks/1/deliveries/2/attempts",
+        )
+        .match_body("")
+        .with_status(202)
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+        let since = "2024-05-02T00:00:00Z".parse().unwrap();
+        let redeliveries = crate::testing::mock_client()
+            .redeliver_failed_repo_hook_deliveries("octocat/redeliver", 1, since)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            redeliveries
+                .iter()
+                .map(|r| (r.delivery.id, r.result.is_ok()))
+                .collect::<Vec<_>>(),
+            vec![(6, false), (2, true)]
+        );
+        rejected.assert();
+        redeliver.assert();
+    }
+
+    const HOOK: &str = r#"{"type": "Repository", "id": 12345678, "name": "web", "active": true,
+        "events": ["push", "pull_request"],
+        "config": {"content_type": "json", "insecure_ssl": "0", "url": "https://example.com/webhook"},
+        "updated_at": "2019-06-03T00:57:16Z", "created_at": "2019-06-03T00:57:16Z",
+        "url": "https://api.github.com/repos/octocat/Hello-World/hooks/12345678",
+        "test_url": "https://api.github.com/repos/octocat/Hello-World/hooks/12345678/test",
+        "ping_url": "https://api.github.com/repos/octocat/Hello-World/hooks/12345678/pings",
+        "last_response": {"code": null, "status": "unused", "message": null}}"#;
+
+    #[tokio::test]
+    async fn creates_repo_hooks() {
+        let mock = mockito::mock("POST", "/repos/octocat/create-hook/hooks")
+            .match_body(Matcher::Json(serde_json::json!({
+                "name": "web",
+                "active": true,
+                "events": ["push", "pull_request"],
+                "config": {
+                    "url": "https://example.com/webhook",
+                    "content_type": "json",
+                    "secret": "shh",
+                    "insecure_ssl": "0"
+                }
+            })))
+            .with_status(201)
+            .with_body(HOOK)
+            .create();
+
+        let hook = CreateHook {
+            events: vec!["push".to_string(), "pull_request".to_string()],
+            ..CreateHook::new(HookConfig {
+                url: Some("https://example.com/webhook".to_string()),
+                content_type: Some(HookContentType::Json),
+                secret: Some("shh".to_string()),
+                insecure_ssl: Some(false),
+            })
+        };
+        let hook = crate::testing::mock_client()
+            .create_repo_hook("octocat/create-hook", &hook)
+            .await
+            .unwrap();
+
+        assert_eq!(hook.config.insecure_ssl, Some(false));
+        assert_eq!(hook.config.content_type, Some(HookContentType::Json));
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn updates_org_hooks() {
+        let mock = mockito::mock("PATCH", "/orgs/octo-org/hooks/12345678")
+            .match_body(Matcher::Json(serde_json::json!({"active": false})))
+            .with_body(HOOK)
+            .create();
+
+        let update = UpdateHook {
+            active: Some(false),
+            ..Default::default()
+        };
+        crate::testing::mock_client()
+            .update_org_hook("octo-org", 12345678, &update)
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn pings_and_deletes_hooks() {
+        let ping = mockito::mock("POST", "/repos/octocat/ping-hook/hooks/1/pings")
+            .match_body("")
+            .with_status(204)
+            .create();
+        let delete = mockito::mock("DELETE", "/orgs/ping-org/hooks/2")
+            .with_status(204)
+            .create();
+
+        let octokit = crate::testing::mock_client();
+        octokit
+            .ping_repo_hook("octocat/ping-hook", 1)
+            .await
+            .unwrap();
+        octokit.delete_org_hook("ping-org", 2).await.unwrap();
+
+        ping.assert();
+        delete.assert();
+    }
+
+    #[test]
+    fn accepts_numeric_insecure_ssl() {
+        let config: HookConfig =
+            serde_json::from_str(r#"{"url": "https://example.com", "insecure_ssl": 1}"#).unwrap();
+        assert_eq!(config.insecure_ssl, Some(true));
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::json!({"url": "https://example.com", "insecure_ssl": "1"})
+        );
+    }
+}
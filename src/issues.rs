@@ -0,0 +1,43 @@
+use crate::client::{decode, Octokit};
+use crate::error::OctokitError;
+use crate::pagination::Paginator;
+use crate::{ApiPreviews, CreateComment, Issue, IssueComment, ID};
+
+impl Octokit {
+    /// DELETE /repos/:owner/:repo/issues/comments/:comment_id
+    pub async fn delete_issue_comment(
+        &self,
+        nwo: &str,
+        comment_number: ID,
+    ) -> Result<(), OctokitError> {
+        let url = self.url(&format!(
+            "/repos/{}/issues/comments/{}",
+            nwo, comment_number
+        ));
+        self.delete(url).await?;
+        Ok(())
+    }
+
+    /// POST /repos/:owner/:repo/issues/:issue_number/comments
+    pub async fn create_issue_comment(
+        &self,
+        issue_number: ID,
+        repo_name: &str,
+        message: String,
+    ) -> Result<IssueComment, OctokitError> {
+        let new_comment = CreateComment { body: message };
+        let url = self.url(&format!(
+            "/repos/{}/issues/{}/comments",
+            repo_name, issue_number
+        ));
+
+        decode(self.post(url, &new_comment, ApiPreviews::Antiope).await?).await
+    }
+
+    /// GET /issues
+    ///
+    /// Issues assigned to the authenticated user across all visible repositories.
+    pub fn get_all_issues(&self) -> Paginator<Issue> {
+        Paginator::new(self, self.url("/issues"))
+    }
+}
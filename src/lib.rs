@@ -1,20 +1,32 @@
-// use chrono::{DateTime, NaiveDateTime, Utc};
-use openssl;
-use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, LINK, USER_AGENT};
-use reqwest::Response;
+#![allow(clippy::upper_case_acronyms)]
+
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
-use std::fs::File;
-use std::io::prelude::*;
-use std::time::{SystemTime, UNIX_EPOCH};
-
-use jsonwebtoken::{encode, Algorithm, Header};
 
 pub mod apps;
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+pub mod checks;
+pub mod client;
 pub mod error;
+pub mod hooks;
+pub mod issues;
+pub mod pagination;
+pub mod pulls;
+pub mod rate_limit;
+pub mod releases;
+pub mod retry;
+#[cfg(test)]
+mod testing;
+mod timestamp;
 pub mod webhooks;
-use error::OctokitError;
+
+pub use auth::create_jwt;
+pub use client::{Octokit, OctokitBuilder};
+pub use pagination::Paginator;
 
 type ID = u64;
 type CommitSha = String;
@@ -22,14 +34,13 @@ type CommitSha = String;
 // TODO replace with proper URI type later
 type URI = String;
 
-// TODO replace with chrone::DateTime
-type DateTime = String;
+// fields of this type must opt in with `#[serde(with = "crate::timestamp")]`, or
+// `crate::timestamp::option` if optional, to also accept epoch seconds
+type DateTime = chrono::DateTime<chrono::Utc>;
 
 /// Global Relay ID for GQL queries with Node ID
 type GRID = String;
 
-type NameWithOwner = String;
-
 #[derive(Serialize, Deserialize, Debug)]
 pub enum OwnerType {
     User,
@@ -37,22 +48,6 @@ pub enum OwnerType {
     Organization,
 }
 
-type Email = String;
-
-#[derive(Serialize, Deserialize, Debug)]
-enum GithubEvent {
-    Integration,
-    Installation,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "lowercase")]
-pub enum GithubEventAction {
-    Created,
-    Removed,
-    Deleted,
-}
-
 enum ApiPreviews {
     Antiope,
     MachineMan,
@@ -61,10 +56,8 @@ enum ApiPreviews {
 impl fmt::Display for ApiPreviews {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ApiPreviews::Antiope => write!(f, "{}", "application/vnd.github.antiope-preview+json"),
-            ApiPreviews::MachineMan => {
-                write!(f, "{}", "application/vnd.github.machine-man-preview+json")
-            }
+            ApiPreviews::Antiope => write!(f, "application/vnd.github.antiope-preview+json"),
+            ApiPreviews::MachineMan => write!(f, "application/vnd.github.machine-man-preview+json"),
         }
     }
 }
@@ -75,9 +68,24 @@ impl ApiPreviews {
     }
 }
 
+/// The error body GitHub sends along with 4xx and 5xx responses.
+/// https://developer.github.com/v3/#client-errors
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ApiError {
     pub message: String,
+    pub documentation_url: Option<URI>,
+    // only present for 422 Unprocessable Entity
+    #[serde(default)]
+    pub errors: Vec<ValidationError>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ValidationError {
+    pub resource: Option<String>,
+    pub field: Option<String>,
+    // missing, missing_field, invalid, already_exists or custom
+    pub code: Option<String>,
+    pub message: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -124,92 +132,47 @@ pub struct Repository {
     pub full_name: String,
 }
 
-// mod webhook_payloads {
-#[derive(Deserialize, Debug)]
-struct CommentPayload {
-    pub action: GithubEventAction,
-    pub issue: Issue,
-    pub repository: Repository,
-    pub comment: IssueComment,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct CommitAuthor {
-    pub name: String,
-    pub email: Email,
-}
-
-/// Used for web-flows etc, that are on behalf of a given user
-type Committer = CommitAuthor;
-
-#[derive(Deserialize, Debug)]
-pub struct Commit {
-    pub id: CommitSha,
-    pub tree_id: CommitSha,
-    pub distinct: bool,
-    pub message: String,
-    pub author: CommitAuthor,
-    pub committer: Committer,
-    pub url: URI,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct Pusher {
-    pub name: String,
-    pub email: Email,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct PushInstallation {
-    pub id: ID,
-    pub node_id: GRID,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct PushPayload {
-    pub r#ref: String,
-    pub before: CommitSha,
-    pub after: CommitSha,
-    pub created: bool,
-    pub deleted: bool,
-    pub forced: bool,
-    pub base_ref: Option<String>,
-    pub compare: URI,
-    pub commits: Vec<Commit>,
-    pub head_commit: Option<Commit>,
-    pub repository: Repository,
-    pub pusher: Pusher,
-    pub sender: User,
-    pub installation: Option<PushInstallation>,
-}
-
-#[derive(Deserialize, Debug)]
-struct IssuePayload {
-    pub issue: Issue,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum PermissionGrant {
     Read,  // GH read-only permission
     Write, // GH read-write permission
+    Admin, // GH admin permission, only used by a few organization permissions
 }
 
-// TODO consider making this a hashmap if no-access is implemented by omitting the key
-#[derive(Serialize, Deserialize, Debug)]
+/// Permissions of an app or installation. No access is expressed by omitting the key.
+///
+/// Also used to request tokens with fewer permissions than the installation
+/// was granted, see [`InstallationTokenRequest`](struct.InstallationTokenRequest.html).
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct InstallationPermissions {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub administration: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub packages: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub statuses: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub issues: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub deployments: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub contents: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub checks: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub vulnerability_alerts: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pull_requests: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pages: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<PermissionGrant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub app_config: Option<PermissionGrant>,
+    /// Permissions without a dedicated field, e.g. `actions` or `workflows`.
+    #[serde(flatten)]
+    pub other: BTreeMap<String, PermissionGrant>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -220,7 +183,7 @@ pub struct PullRequest {
     pub state: String,
     pub locked: bool,
     pub title: String,
-    pub body: String,
+    pub body: Option<String>,
     pub user: User,
     pub node_id: GRID,
     pub html_url: URI,
@@ -234,22 +197,41 @@ pub struct PullRequest {
     pub statuses_url: URI,
 }
 
-#[derive(Deserialize, Debug)]
+/// The few fields of a pull request that check suites and check runs carry.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PullRequestRef {
+    pub url: URI,
+    pub id: ID,
+    pub number: u64,
+    pub head: PullRequestBranch,
+    pub base: PullRequestBranch,
+}
+
+/// The branch a pull request merges from (`head`) or into (`base`).
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PullRequestBranch {
+    pub r#ref: String,
+    pub sha: CommitSha,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct GithubApp {
     pub id: ID,
     pub slug: String,
     pub node_id: GRID,
     pub owner: User,
     pub name: String,
-    pub description: String,
+    pub description: Option<String>,
     pub external_url: URI,
     pub html_url: URI,
+    #[serde(with = "crate::timestamp")]
     pub created_at: DateTime,
+    #[serde(with = "crate::timestamp")]
     pub updated_at: DateTime,
     // TODO should we just use a HashMap to supports it's dynamic nature?
     //    pub permissions: HashMap<String, PermissionGrant>,
     pub permissions: InstallationPermissions,
-    pub evens: Vec<String>,
+    pub events: Vec<String>,
     pub installations_count: Option<u64>, // only included in authenticated calls
 }
 
@@ -258,25 +240,17 @@ pub struct GithubApp {
 pub struct CheckSuite {
     pub id: ID,
     pub node_id: GRID,
-    pub head_branch: String,
+    pub head_branch: Option<String>,
     pub head_sha: CommitSha,
     pub status: String,
-    pub conclusion: String,
+    pub conclusion: Option<String>,
     pub url: URI,
-    pub before: CommitSha,
-    pub after: CommitSha,
-    pub pull_requests: Vec<PullRequest>,
+    pub before: Option<CommitSha>,
+    pub after: Option<CommitSha>,
+    pub pull_requests: Vec<PullRequestRef>,
     pub app: GithubApp,
-    pub repository: Repository,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct InstallationRepository {
-    pub id: ID,
-    pub node_id: ID,
-    pub name: String,
-    pub full_name: String,
-    pub private: bool,
+    // not included in webhook payloads, which carry the repository next to the suite
+    pub repository: Option<Repository>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -292,21 +266,14 @@ pub struct Installation {
     pub target_type: OwnerType,
     pub permissions: InstallationPermissions,
     pub events: Vec<String>,
-    //    pub created_at: DateTime, // Ignore! because they are delivered as timestamps
-    //    pub updated_at: DateTime, // Ignore! because they are delivered as timestamps
+    // webhook payloads send these as epoch seconds
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime,
+    #[serde(with = "crate::timestamp")]
+    pub updated_at: DateTime,
     pub single_file_name: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct InstallationPayload {
-    // FIXME create event-action enum
-    pub action: GithubEventAction,
-    pub installation: Installation,
-    // in case of deleted installs there is no repository key
-    pub repositories: Option<Vec<InstallationRepository>>,
-    pub sender: User,
-}
-
 // TODO create Review and ReviewComment payloads
 // struct Review
 #[derive(Serialize, Deserialize, Debug)]
@@ -327,308 +294,137 @@ pub struct ReviewComment {
     // original_commit_id: CommitSha,
     // in_reply_to_id: ID,
     // author_association: String
-    // created_at: DateTime,
-    // updated_at: DateTime,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime,
+    #[serde(with = "crate::timestamp")]
+    pub updated_at: DateTime,
     // html_url: URI,
     // pull_request_url: URI,
     // url: URI,
 }
 
+/// https://developer.github.com/v3/repos/releases/#response-for-successful-upload
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReleaseAsset {
+    pub id: ID,
+    pub node_id: GRID,
+    pub name: String,
+    pub label: Option<String>,
+    pub content_type: String,
+    pub state: String,
+    pub size: u64,
+    pub download_count: u64,
+    pub url: URI,
+    pub browser_download_url: URI,
+    pub uploader: User,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime,
+    #[serde(with = "crate::timestamp")]
+    pub updated_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Release {
+    pub id: ID,
+    pub node_id: GRID,
+    pub tag_name: String,
+    pub target_commitish: String,
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+    pub author: User,
+    pub assets: Vec<ReleaseAsset>,
+    pub url: URI,
+    pub html_url: URI,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime,
+    #[serde(default, with = "crate::timestamp::option")]
+    pub published_at: Option<DateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WorkflowRun {
+    pub id: ID,
+    pub name: Option<String>,
+    pub workflow_id: ID,
+    pub run_number: u64,
+    pub event: String,
+    pub head_branch: Option<String>,
+    pub head_sha: CommitSha,
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+    pub url: URI,
+    pub html_url: URI,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime,
+    #[serde(with = "crate::timestamp")]
+    pub updated_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WorkflowJob {
+    pub id: ID,
+    pub run_id: ID,
+    pub name: String,
+    pub head_sha: CommitSha,
+    pub status: String,
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub url: URI,
+    pub html_url: Option<URI>,
+    #[serde(with = "crate::timestamp")]
+    pub started_at: DateTime,
+    #[serde(default, with = "crate::timestamp::option")]
+    pub completed_at: Option<DateTime>,
+}
+
 // payloads for Create and Update
 #[derive(Serialize, Debug)]
 struct CreateComment {
     body: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct InstallationToken {
     pub token: String,
+    #[serde(with = "crate::timestamp")]
     pub expires_at: DateTime,
     pub permissions: InstallationPermissions,
+    /// `all` or `selected`
+    pub repository_selection: Option<String>,
     // omitted if repository_ids is not set in the request
     pub repositories: Option<Vec<Repository>>,
 }
 
-fn perform_get(
-    token: &String,
-    url: URI,
-    token_type: AuthTokenType,
-) -> Result<Response, reqwest::Error> {
-    let client = reqwest::Client::new();
-
-    client
-        .get(&url[..])
-        .header(USER_AGENT, "Octokit/Rust v0.1.0")
-        .header(ACCEPT, "application/vnd.github.antiope-preview+json")
-        .header(
-            AUTHORIZATION,
-            String::from(format!("{} {}", token_type, token)),
-        )
-        .send()
-}
-
-fn perform_delete(token: &String, url: URI) -> Result<Response, reqwest::Error> {
-    let client = reqwest::Client::new();
-    client
-        .delete(&url[..])
-        .header(USER_AGENT, "Octokit/Rust v0.1.0")
-        .header(CONTENT_TYPE, "application/vnd.github.antiope-preview+json")
-        .header(AUTHORIZATION, String::from(format!("token {}", token)))
-        .send()
-}
-
-// TODO consider using custom types instead of reqwest types
-fn perform_post<T: Serialize>(
-    token: &String,
-    url: URI,
-    data: &T,
-    token_type: AuthTokenType,
-    media_type: String,
-) -> Result<Response, reqwest::Error> {
-    println!(
-        "media type is: {}, token_type is: {}",
-        media_type, token_type
-    );
-    let client = reqwest::Client::new();
-    let result = client
-        .post(&url[..])
-        .header(USER_AGENT, "Octokit/Rust v0.1.0")
-        .header(ACCEPT, media_type)
-        .header(
-            AUTHORIZATION,
-            String::from(format!("{} {}", token_type, token)),
-        )
-        .json(&data)
-        .send();
-
-    // just for debugging
-    result
-}
-
-/// DELETE /repos/:owner/:repo/issues/comments/:comment_id
-pub fn delete_issue_comment(token: &String, nwo: &NameWithOwner, comment_number: ID) {
-    let url: String = format!(
-        "https://api.github.com/repos/{}/issues/comments/{}",
-        nwo, comment_number
-    );
-    let result = perform_delete(token, url);
-    match result {
-        Ok(_) => {
-            println! {"DELETE succeeded"};
-        }
-        Err(err) => {
-            println!("DELETE failed {:}", err);
-        }
-    }
-}
-
-pub fn create_issue_comment(
-    token: &String,
-    issue_number: ID,
-    repo_name: &String,
-    message: String,
-) -> Result<IssueComment, OctokitError> {
-    let new_comment = CreateComment { body: message };
-    let url: String = format!(
-        "https://api.github.com/repos/{}/issues/{}/comments",
-        repo_name, issue_number
-    );
-
-    let res = perform_post(
-        &token,
-        url,
-        &new_comment,
-        AuthTokenType::Token,
-        ApiPreviews::Antiope.to_media_type(),
-    );
-    match res {
-        Ok(mut response) => {
-            println!("Request succeeded {:?}", response);
-            let created_comment: IssueComment = response.json().expect("JSON parse failed");
-            println!("{:?}", created_comment);
-            return Ok(created_comment);
-        }
-        Err(err) => {
-            println!("Request failed {:?}", err);
-            return Err(OctokitError::from(err));
-        }
-    }
-}
-
-/// GET /repos/:owner/:repo/pulls/:pull_number/comments
-pub fn get_review_comments(
-    token: &String,
-    nwo: NameWithOwner,
-    pull_number: ID,
-) -> Option<Vec<ReviewComment>> {
-    let url: String = format!(
-        "https://api.github.com/repos/{}/pulls/{}/comments",
-        nwo, pull_number
-    );
-    // TODO replace naive conversion with proper error handling
-    let result = perform_get(&token, url, AuthTokenType::Token);
-    match result {
-        Ok(mut response) => {
-            println!("Request succeeded :D {:?}", response);
-            let data: Vec<ReviewComment> = response.json().expect("decoding comments failed");
-            println!("Data: {:?}", data);
-            Some(data)
-        }
-        Err(err) => {
-            println!("Request failed {:?}", err);
-            None
-        }
-    }
-}
-
-pub fn get_issue_batch(_token: &String) {
-    unimplemented!()
-}
-pub fn get_pull_requests(_token: &String, _nwo: &String) {
-    unimplemented!()
-}
-
-// Status: TODO
-//TODO paginate over all issues later
-pub fn get_all_issues(token: &String) {
-    get_issue_batch(&token);
-}
-
-// Status: WIP (partially done)
-// GET /repos/:owner/:repo/pulls/comments
-pub fn get_all_review_comments(token: &String, nwo: &NameWithOwner) -> Option<Vec<ReviewComment>> {
-    let url: String = format!("https://api.github.com/repos/{}/comments", nwo);
-    let result = perform_get(&token, url, AuthTokenType::Token);
-
-    match result {
-        Ok(mut response) => {
-            println!("Request succeeded :D {:?}", response);
-            let link_header = response.headers().get(LINK);
-            if link_header.is_some() {
-                println!("link header: {:?}", link_header);
-            }
-            let data: Vec<ReviewComment> = response.json().expect("decoding comments failed");
-            println!("Data: {:?}", data);
-            Some(data)
-        }
-        Err(err) => {
-            println!("Request failed {:?}", err);
-            None
-        }
-    }
-}
-
-type GithubAppId = String;
-
-/// Well-known JWT claims
-///    iss (issuer): Issuer of the JWT
-///    exp (expiration time): Time after which the JWT expires
-///    iat (issued at time): Time at which the JWT was issued; can be used to determine age of the JWT
-///    sub (subject): Subject of the JWT (the user)
-///    aud (audience): Recipient for which the JWT is intended
-///    nbf (not before time): Time before which the JWT must not be accepted for processing
-///    jti (JWT ID): Unique identifier; can be used to prevent the JWT from being replayed (allows a token to be used only once)
-// Implement the minimum required by Github (for now)
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    iat: u64,
-    exp: u64,
-    iss: GithubAppId,
-}
-
-enum AuthTokenType {
-    Token,
-    JWT,
-}
-
-impl fmt::Display for AuthTokenType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            AuthTokenType::Token => write!(f, "{}", "token"),
-            AuthTokenType::JWT => write!(f, "{}", "Bearer"),
-        }
-    }
-}
-
-/// See: https://developer.github.com/apps/building-github-apps/authenticating-with-github-apps/#authenticating-as-a-github-app
-/// Github expects RS256 encoded JWTs
-/// ISS has to be the APP_ID
-/// Private key is in PKCS#1 RSAPrivateKey format
-/// https://developer.github.com/apps/building-github-apps/authenticating-with-github-apps/#generating-a-private-key
-pub fn create_jwt(path: &str, app_id: &String) -> std::result::Result<String, String> {
-    println!("opening secret key file: {}", path);
-    let mut file = File::open(path).map_err(|_| "failed to open file".to_string())?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .map_err(|_| "Failed t read from file".to_string())?;
-
-    let now = SystemTime::now();
-    let iat = now
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs();
-    let exp = iat + 60 * 10; // 10 minute validity
-    let iss = app_id;
-
-    //    openssl.private_key_from_pem
-    let key = openssl::rsa::Rsa::private_key_from_pem(contents.as_bytes())
-        .map_err(|_| "Openssl died".to_string())?;
-    let der = key
-        .private_key_to_der()
-        .map_err(|_| "creating der failed")?;
-
-    let claims = Claims {
-        iat: iat,
-        exp: exp,
-        iss: iss.to_string(),
-    };
-    let mut header = Header::default();
-    header.alg = Algorithm::RS256;
-    let token = encode(&header, &claims, der.as_ref()).map_err(|_| "JWT encoding failed")?;
-
-    return Ok(token);
-}
-
-///  curl -i -H "Authorization: Bearer YOUR_JWT"
-///          -H "Accept: application/vnd.github.machine-man-preview+json"
-///          https://api.github.com/app
-pub fn get_app(token: &String) -> Option<GithubApp> {
-    let result = perform_get(
-        &token,
-        "https://api.github.com/app".to_string(),
-        AuthTokenType::JWT,
-    );
-    //    println!("result is: {:?}", result);
-    match result {
-        Ok(mut response) => {
-            if response.status() == reqwest::StatusCode::OK {
-                let app: GithubApp = response.json().unwrap();
-                return Some(app);
-            } else if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-                let body: ApiError = response.json().expect("Its all broken");
-                println!("{:?}", body);
-                return None;
-            } else {
-                let status = response.headers().get("status").unwrap();
-                println!("Request failed with status: {:?}", status);
-                return None;
-            }
-        }
-        Err(error) => {
-            println!("request failed. {:?}", error);
-            return None;
-        }
-    }
-}
-
-/// GET /app/installations/:installation_id
-pub fn get_installation() -> () {}
-
-#[derive(Serialize, Debug)]
-struct CreateInstallationToken {
+/// Narrows down what an installation token may access.
+///
+/// The default requests a token with all permissions and repositories of the
+/// installation. Tokens can only be scoped down, never beyond what the
+/// installation was granted.
+///
+/// ```
+/// use octokit::{InstallationPermissions, InstallationTokenRequest, PermissionGrant};
+///
+/// let request = InstallationTokenRequest {
+///     repositories: Some(vec!["hello-world".to_string()]),
+///     permissions: Some(InstallationPermissions {
+///         checks: Some(PermissionGrant::Write),
+///         ..Default::default()
+///     }),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct InstallationTokenRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
-    repository_ids: Option<Vec<ID>>,
-    permissions: HashMap<String, PermissionGrant>,
+    pub repository_ids: Option<Vec<ID>>,
+    /// Repository names without the owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repositories: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<InstallationPermissions>,
 }
 
 #[derive(Serialize, Debug)]
@@ -636,47 +432,6 @@ struct CreateCheckSuite {
     head_sha: CommitSha,
 }
 
-///  POST /repos/:owner/:repo/check-suites
-pub fn create_check_suite(
-    token: &String,
-    nwo: &NameWithOwner,
-    sha: CommitSha,
-) -> Option<CheckSuite> {
-    let data = CreateCheckSuite {
-        head_sha: sha.to_string(),
-    };
-
-    let result = perform_post(
-        &token,
-        format!("https://api.github.com/repos/{}/check-suites", nwo),
-        &data,
-        AuthTokenType::Token,
-        ApiPreviews::Antiope.to_media_type(),
-    );
-
-    println!("result is: {:?}", result);
-    match result {
-        Ok(mut response) => {
-            if response.status() == reqwest::StatusCode::OK {
-                let check_suite: CheckSuite = response.json().unwrap();
-                return Some(check_suite);
-            } else if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-                let body: ApiError = response.json().expect("Its all broken");
-                println!("{:?}", body);
-                return None;
-            } else {
-                let status = response.headers().get("status").unwrap();
-                println!("Request failed with status: {:?}", status);
-                return None;
-            }
-        }
-        Err(error) => {
-            println!("request failed. {:?}", error);
-            return None;
-        }
-    }
-}
-
 #[derive(Serialize, Debug)]
 pub struct CreateCheckRun {
     pub name: String,
@@ -685,7 +440,7 @@ pub struct CreateCheckRun {
     //    conclusion: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct CheckRun {
     pub id: ID,
     pub head_sha: CommitSha,
@@ -693,107 +448,203 @@ pub struct CheckRun {
     pub external_id: String,
     pub url: URI,
     pub html_url: URI,
-    pub details_url: URI,
+    pub details_url: Option<URI>,
     pub status: String,
     pub conclusion: Option<String>,
+    #[serde(with = "crate::timestamp")]
     pub started_at: DateTime,
+    #[serde(default, with = "crate::timestamp::option")]
     pub completed_at: Option<DateTime>,
+    pub pull_requests: Vec<PullRequestRef>,
 }
 
-///  POST /repos/:owner/:repo/check-runs
-pub fn create_check_run(token: &String, nwo: &NameWithOwner, sha: CommitSha) -> Option<CheckRun> {
-    let data = CreateCheckRun {
-        name: String::from("Example Check-Run"),
-        head_sha: sha.to_string(),
-    };
-
-    let result = perform_post(
-        &token,
-        format!("https://api.github.com/repos/{}/check-runs", nwo),
-        &data,
-        AuthTokenType::Token,
-        ApiPreviews::Antiope.to_media_type(),
-    );
-
-    println!("result is: {:?}", result);
-    match result {
-        Ok(mut response) => {
-            if response.status() == reqwest::StatusCode::CREATED {
-                let check_run: CheckRun = response.json().unwrap();
-                return Some(check_run);
-            } else if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-                let body: ApiError = response.json().expect("Its all broken");
-                println!("{:?}", body);
-                return None;
-            } else {
-                let status = response.headers().get("status").unwrap();
-                println!("Request failed with status: {:?}", status);
-                return None;
-            }
-        }
-        Err(error) => {
-            println!("Request failed. {:?}", error);
-            return None;
-        }
+/// An attempt to deliver a webhook, as listed by the hook deliveries endpoints.
+/// Only deliveries fetched one by one carry the `request` and `response`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HookDelivery {
+    pub id: ID,
+    /// The `X-GitHub-Delivery` header, shared by all attempts of a delivery.
+    pub guid: String,
+    #[serde(with = "crate::timestamp")]
+    pub delivered_at: DateTime,
+    pub redelivery: bool,
+    /// Seconds until the receiver answered.
+    pub duration: f64,
+    /// `OK` or a description of the failure such as `Invalid HTTP Response: 503`.
+    pub status: String,
+    /// The status the receiver answered with, `0` if it could not be reached.
+    pub status_code: u16,
+    pub event: String,
+    pub action: Option<String>,
+    pub installation_id: Option<ID>,
+    pub repository_id: Option<ID>,
+    pub url: Option<URI>,
+    pub request: Option<HookDeliveryRequest>,
+    pub response: Option<HookDeliveryResponse>,
+}
+
+impl HookDelivery {
+    /// Whether the receiver answered with a `2xx` status.
+    pub fn succeeded(&self) -> bool {
+        (200..300).contains(&self.status_code)
     }
 }
 
-/// POST /app/installations/:installation_id/access_tokens
-pub fn create_installation_token(jwt: String, installation_id: ID) -> Option<String> {
-    let mut permissions = HashMap::new();
-    permissions.insert(String::from("checks"), PermissionGrant::Write);
-
-    // only allow a certain list of repositories. Not all
-    //    let repository_ids = Some(vec![]);
-    let repository_ids = None;
-
-    let data = CreateInstallationToken {
-        repository_ids,
-        permissions,
-    };
-
-    let result = perform_post(
-        &jwt,
-        format!(
-            "https://api.github.com/app/installations/{}/access_tokens",
-            installation_id
-        ),
-        &data,
-        AuthTokenType::JWT,
-        ApiPreviews::MachineMan.to_media_type(),
-    );
-
-    println!("result is: {:?}", result);
-    match result {
-        Ok(mut response) => {
-            // We expect a 201
-            if response.status() == reqwest::StatusCode::CREATED {
-                let token_data: InstallationToken = response.json().unwrap();
-                println!("{:?}", token_data);
-                return Some(token_data.token);
-            } else if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-                let body: ApiError = response.json().expect("Its all broken");
-                println!("{:?}", body);
-                return None;
-            } else {
-                let status = response.headers().get("status").unwrap();
-                println!("Request failed with status: {:?}", status);
-                return None;
-            }
-        }
-        Err(error) => {
-            println!("Request failed. {:?}", error);
-            return None;
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HookDeliveryRequest {
+    pub headers: Option<HashMap<String, String>>,
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HookDeliveryResponse {
+    pub headers: Option<HashMap<String, String>>,
+    pub payload: Option<String>,
+}
+
+/// A repository or organization webhook.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Hook {
+    pub id: ID,
+    /// `Repository`, `Organization` or `App`.
+    pub r#type: String,
+    /// Always `web`.
+    pub name: String,
+    pub active: bool,
+    pub events: Vec<String>,
+    pub config: HookConfig,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime,
+    #[serde(with = "crate::timestamp")]
+    pub updated_at: DateTime,
+    // the hook embedded in ping events of apps carries no URLs
+    pub url: Option<URI>,
+    pub test_url: Option<URI>,
+    pub ping_url: Option<URI>,
+    pub deliveries_url: Option<URI>,
+    pub last_response: Option<HookLastResponse>,
+}
+
+/// Where and how deliveries of a hook are sent.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HookConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<URI>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<HookContentType>,
+    /// The secret deliveries are signed with. GitHub returns `********`
+    /// instead of the secret.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Whether TLS certificates are not verified, sent as `"0"` or `"1"`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "insecure_ssl"
+    )]
+    pub insecure_ssl: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HookContentType {
+    Json,
+    /// `application/x-www-form-urlencoded`, the payload is sent in a `payload` field.
+    Form,
+}
+
+/// GitHub sends `insecure_ssl` as a string but also accepts numbers.
+mod insecure_ssl {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Text(String),
+        Number(u8),
+    }
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<bool>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(true) => serializer.serialize_str("1"),
+            Some(false) => serializer.serialize_str("0"),
+            None => serializer.serialize_none(),
         }
     }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<bool>, D::Error> {
+        Ok(match Option::<Flag>::deserialize(deserializer)? {
+            Some(Flag::Text(text)) => Some(text == "1"),
+            Some(Flag::Number(number)) => Some(number == 1),
+            None => None,
+        })
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HookLastResponse {
+    pub code: Option<u16>,
+    /// `active`, `unused` or an error description.
+    pub status: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Creates a repository or organization webhook.
+///
+/// ```
+/// use octokit::{CreateHook, HookConfig, HookContentType};
+///
+/// let hook = CreateHook {
+///     events: vec!["push".to_string(), "pull_request".to_string()],
+///     ..CreateHook::new(HookConfig {
+///         url: Some("https://example.com/webhooks".to_string()),
+///         content_type: Some(HookContentType::Json),
+///         secret: Some("secret".to_string()),
+///         insecure_ssl: Some(false),
+///     })
+/// };
+/// ```
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateHook {
+    /// Must be `web`.
+    pub name: String,
+    pub config: HookConfig,
+    pub events: Vec<String>,
+    pub active: bool,
+}
+
+impl CreateHook {
+    /// An active hook for `push` events, like GitHub creates by default.
+    pub fn new(config: HookConfig) -> CreateHook {
+        CreateHook {
+            name: "web".to_string(),
+            config,
+            events: vec!["push".to_string()],
+            active: true,
+        }
     }
-    // Write tests for JWT logic (no API mocks needed)
-    // Write tests for error cases for non-API functions
+}
+
+/// Changes a webhook, fields left at `None` are kept.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct UpdateHook {
+    /// Replaces the whole config, the secret is removed unless it is sent again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<HookConfig>,
+    /// Replaces the events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<String>>,
+    /// Events to add, only supported for repository hooks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub add_events: Option<Vec<String>>,
+    /// Events to remove, only supported for repository hooks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove_events: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
 }
@@ -0,0 +1,308 @@
+use futures_util::stream::{self, Stream};
+use reqwest::header::LINK;
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+
+use crate::client::{decode, Octokit};
+use crate::error::OctokitError;
+use crate::URI;
+
+/// The relations of a `Link` response header.
+///
+/// ```text
+/// Link: <https://api.github.com/repositories/1/issues?page=2>; rel="next",
+///       <https://api.github.com/repositories/1/issues?page=5>; rel="last"
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Links {
+    pub next: Option<URI>,
+    pub prev: Option<URI>,
+    pub first: Option<URI>,
+    pub last: Option<URI>,
+}
+
+impl Links {
+    pub fn parse(header: &str) -> Links {
+        let mut links = Links::default();
+        for link in header.split(',') {
+            let mut parts = link.split(';');
+            let url = match parts.next().map(str::trim) {
+                Some(url) if url.starts_with('<') && url.ends_with('>') => {
+                    url[1..url.len() - 1].to_string()
+                }
+                _ => continue,
+            };
+            for param in parts {
+                let param = param.trim();
+                if !param.starts_with("rel=") {
+                    continue;
+                }
+                // a single link may carry several relations, e.g. rel="next last"
+                for rel in param[4..].trim_matches('"').split_whitespace() {
+                    match rel {
+                        "next" => links.next = Some(url.clone()),
+                        "prev" => links.prev = Some(url.clone()),
+                        "first" => links.first = Some(url.clone()),
+                        "last" => links.last = Some(url.clone()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        links
+    }
+
+    fn from_response(response: &Response) -> Links {
+        response
+            .headers()
+            .get(LINK)
+            .and_then(|value| value.to_str().ok())
+            .map(Links::parse)
+            .unwrap_or_default()
+    }
+}
+
+/// Lazily walks a paginated list endpoint by following `rel="next"` links.
+///
+/// Pages are only requested once the items of the previous page are consumed,
+/// so stopping early saves requests. Use [`into_stream`](#method.into_stream)
+/// to get a `Stream` of items.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), octokit::error::OctokitError> {
+/// # let octokit = octokit::Octokit::new("my-token")?;
+/// let mut comments = octokit.get_all_review_comments("rust-lang/rust").per_page(100);
+/// while let Some(comment) = comments.next_item().await {
+///     println!("{}", comment?.body);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Paginator<T> {
+    client: Octokit,
+    next: Option<URI>,
+    last: Option<URI>,
+    items: std::vec::IntoIter<T>,
+    pages_fetched: usize,
+    max_pages: Option<usize>,
+}
+
+impl<T: DeserializeOwned> Paginator<T> {
+    pub(crate) fn new(client: &Octokit, url: URI) -> Paginator<T> {
+        Paginator {
+            client: client.clone(),
+            next: Some(url),
+            last: None,
+            items: Vec::new().into_iter(),
+            pages_fetched: 0,
+            max_pages: None,
+        }
+    }
+
+    /// Number of items GitHub returns per page, at most 100.
+    ///
+    /// Only affects pages that have not been requested yet.
+    pub fn per_page(mut self, per_page: u8) -> Self {
+        self.next = self
+            .next
+            .map(|url| set_query_param(&url, "per_page", &per_page.min(100).to_string()));
+        self
+    }
+
+    /// Starts at `cursor` instead of the first page, for endpoints with
+    /// cursor based pagination such as the hook deliveries.
+    pub fn cursor(mut self, cursor: &str) -> Self {
+        self.next = self.next.map(|url| set_query_param(&url, "cursor", cursor));
+        self
+    }
+
+    /// The cursor of the next page, to resume from later with
+    /// [`cursor`](#method.cursor). `None` for page-number based pagination.
+    pub fn next_cursor(&self) -> Option<String> {
+        self.next
+            .as_ref()
+            .and_then(|url| query_param(url, "cursor"))
+    }
+
+    /// Stop after `max_pages` pages, even if GitHub announces more.
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Number of pages requested so far.
+    pub fn pages_fetched(&self) -> usize {
+        self.pages_fetched
+    }
+
+    /// URL of the last page, as announced by the most recent response.
+    pub fn last_page_url(&self) -> Option<&str> {
+        self.last.as_deref()
+    }
+
+    /// Number of the last page, useful to estimate progress.
+    ///
+    /// Only known once the first page has been fetched, and only for
+    /// page-number based pagination.
+    pub fn last_page(&self) -> Option<u64> {
+        self.last
+            .as_ref()
+            .and_then(|url| query_param(url, "page"))
+            .and_then(|page| page.parse().ok())
+    }
+
+    /// Fetches the next page as a whole, skipping any buffered items.
+    ///
+    /// Returns `None` once there are no more pages or `max_pages` is reached.
+    pub async fn next_page(&mut self) -> Option<Result<Vec<T>, OctokitError>> {
+        if self.max_pages.is_some_and(|max| self.pages_fetched >= max) {
+            return None;
+        }
+        let url = self.next.take()?;
+        self.pages_fetched += 1;
+
+        let response = match self.client.get(url).await {
+            Ok(response) => response,
+            Err(err) => return Some(Err(err)),
+        };
+        let links = Links::from_response(&response);
+        self.next = links.next;
+        if links.last.is_some() {
+            self.last = links.last;
+        }
+        Some(decode(response).await)
+    }
+
+    /// Returns the next item, fetching the next page if the current one is exhausted.
+    pub async fn next_item(&mut self) -> Option<Result<T, OctokitError>> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(Ok(item));
+            }
+            match self.next_page().await? {
+                Ok(items) => self.items = items.into_iter(),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Turns the paginator into a `Stream` of all items across pages.
+    pub fn into_stream(self) -> impl Stream<Item = Result<T, OctokitError>> {
+        stream::unfold(self, |mut paginator| async move {
+            let item = paginator.next_item().await?;
+            Some((item, paginator))
+        })
+    }
+}
+
+fn query_param(url: &str, key: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()?
+        .query_pairs()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.into_owned())
+}
+
+fn set_query_param(url: &str, key: &str, value: &str) -> URI {
+    let mut parsed = match reqwest::Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return url.to_string(),
+    };
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| name != key)
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    parsed
+        .query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(key, value);
+    parsed.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::TryStreamExt;
+
+    #[test]
+    fn parses_link_header() {
+        let links = Links::parse(
+            r#"<https://api.github.com/repositories/1/issues?page=2>; rel="next", <https://api.github.com/repositories/1/issues?page=5>; rel="last""#,
+        );
+        assert_eq!(
+            links.next.as_deref(),
+            Some("https://api.github.com/repositories/1/issues?page=2")
+        );
+        assert_eq!(
+            links.last.as_deref(),
+            Some("https://api.github.com/repositories/1/issues?page=5")
+        );
+        assert_eq!(links.prev, None);
+    }
+
+    #[tokio::test]
+    async fn follows_next_links_across_pages() {
+        let comment = |id: u64| {
+            format!(
+                r#"{{"id": {}, "body": "c", "user": {{"id": 1, "login": "octocat", "node_id": "MDQ", "type": "User", "site_admin": false}},
+                    "created_at": "2011-04-14T16:00:49Z", "updated_at": "2011-04-14T16:00:49Z"}}"#,
+                id
+            )
+        };
+        let first = mockito::mock("GET", "/repos/octocat/paged/pulls/comments?per_page=2")
+            .with_header(
+                "link",
+                &format!(
+                    r#"<{0}/repos/octocat/paged/pulls/comments?per_page=2&page=2>; rel="next", <{0}/repos/octocat/paged/pulls/comments?per_page=2&page=2>; rel="last""#,
+                    mockito::server_url()
+                ),
+            )
+            .with_body(format!("[{}, {}]", comment(1), comment(2)))
+            .create();
+        let second = mockito::mock(
+            "GET",
+            "/repos/octocat/paged/pulls/comments?per_page=2&page=2",
+        )
+        .with_body(format!("[{}]", comment(3)))
+        .create();
+
+        let octokit = crate::testing::mock_client();
+        let mut comments = octokit.get_all_review_comments("octocat/paged").per_page(2);
+        let mut ids = Vec::new();
+        while let Some(comment) = comments.next_item().await {
+            ids.push(comment.unwrap().id);
+        }
+
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(comments.pages_fetched(), 2);
+        assert_eq!(comments.last_page(), Some(2));
+        first.assert();
+        second.assert();
+    }
+
+    #[tokio::test]
+    async fn stops_at_max_pages() {
+        let _mock = mockito::mock("GET", "/repos/octocat/capped/pulls")
+            .with_header(
+                "link",
+                &format!(
+                    r#"<{}/repos/octocat/capped/pulls?page=2>; rel="next""#,
+                    mockito::server_url()
+                ),
+            )
+            .with_body("[]")
+            .create();
+
+        let octokit = crate::testing::mock_client();
+        let pulls: Vec<_> = octokit
+            .get_pull_requests("octocat/capped")
+            .max_pages(1)
+            .into_stream()
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(pulls.is_empty());
+    }
+}
@@ -0,0 +1,23 @@
+use crate::client::Octokit;
+use crate::pagination::Paginator;
+use crate::{PullRequest, ReviewComment, ID};
+
+impl Octokit {
+    /// GET /repos/:owner/:repo/pulls/:pull_number/comments
+    pub fn get_review_comments(&self, nwo: &str, pull_number: ID) -> Paginator<ReviewComment> {
+        let url = self.url(&format!("/repos/{}/pulls/{}/comments", nwo, pull_number));
+        Paginator::new(self, url)
+    }
+
+    /// GET /repos/:owner/:repo/pulls
+    pub fn get_pull_requests(&self, nwo: &str) -> Paginator<PullRequest> {
+        Paginator::new(self, self.url(&format!("/repos/{}/pulls", nwo)))
+    }
+
+    /// GET /repos/:owner/:repo/pulls/comments
+    ///
+    /// Review comments across all pull requests of a repository.
+    pub fn get_all_review_comments(&self, nwo: &str) -> Paginator<ReviewComment> {
+        Paginator::new(self, self.url(&format!("/repos/{}/pulls/comments", nwo)))
+    }
+}
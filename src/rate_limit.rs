@@ -0,0 +1,234 @@
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::client::{decode, Octokit};
+use crate::error::OctokitError;
+
+/// GitHub asks clients to wait at least a minute after hitting a secondary
+/// rate limit without a `Retry-After` header.
+const SECONDARY_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The state of one rate limit bucket, as reported by the `X-RateLimit-*`
+/// response headers or the `GET /rate_limit` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    #[serde(default)]
+    pub used: u64,
+    /// UTC epoch second the bucket resets at.
+    pub reset: u64,
+    /// The bucket the request was counted against, e.g. `core` or `search`.
+    #[serde(default)]
+    pub resource: Option<String>,
+}
+
+impl RateLimit {
+    /// Parses the `X-RateLimit-*` headers, if the response carried them.
+    pub fn from_headers(headers: &HeaderMap) -> Option<RateLimit> {
+        Some(RateLimit {
+            limit: header_u64(headers, "x-ratelimit-limit")?,
+            remaining: header_u64(headers, "x-ratelimit-remaining")?,
+            used: header_u64(headers, "x-ratelimit-used").unwrap_or_default(),
+            reset: header_u64(headers, "x-ratelimit-reset")?,
+            resource: headers
+                .get("x-ratelimit-resource")
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
+        })
+    }
+
+    /// Time left until the bucket resets, zero if it already did.
+    pub fn reset_in(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Duration::from_secs(self.reset.saturating_sub(now))
+    }
+}
+
+/// Parses the `Retry-After` header GitHub sends with secondary rate limits.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    header_u64(headers, "retry-after").map(Duration::from_secs)
+}
+
+pub(crate) fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// What the client does when GitHub reports an exhausted rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RateLimitPolicy {
+    /// Return [`OctokitError::RateLimited`](../error/enum.OctokitError.html) right away.
+    #[default]
+    Fail,
+    /// Sleep until the limit resets (or for `Retry-After` on secondary limits)
+    /// and try again, unless that would take longer than `max_wait`.
+    Wait { max_wait: Duration },
+}
+
+impl RateLimitPolicy {
+    /// How long to sleep before retrying after `err`, `None` to give up.
+    pub(crate) fn delay_for(&self, err: &OctokitError) -> Option<Duration> {
+        let max_wait = match self {
+            RateLimitPolicy::Fail => return None,
+            RateLimitPolicy::Wait { max_wait } => *max_wait,
+        };
+        let delay = match err {
+            OctokitError::RateLimited {
+                retry_after: Some(retry_after),
+                ..
+            } => *retry_after,
+            OctokitError::RateLimited {
+                rate_limit: Some(rate_limit),
+                ..
+            } if rate_limit.remaining == 0 => rate_limit.reset_in() + Duration::from_secs(1),
+            OctokitError::RateLimited { .. } => SECONDARY_RATE_LIMIT_BACKOFF,
+            _ => return None,
+        };
+        if delay <= max_wait {
+            Some(delay)
+        } else {
+            None
+        }
+    }
+}
+
+/// Response of `GET /rate_limit`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RateLimitStatus {
+    pub resources: RateLimitResources,
+    pub rate: RateLimit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RateLimitResources {
+    pub core: RateLimit,
+    pub search: RateLimit,
+    pub graphql: Option<RateLimit>,
+    pub integration_manifest: Option<RateLimit>,
+    pub code_scanning_upload: Option<RateLimit>,
+}
+
+impl Octokit {
+    /// GET /rate_limit
+    ///
+    /// Checking the rate limit does not count against it.
+    pub async fn rate_limit(&self) -> Result<RateLimitStatus, OctokitError> {
+        decode(self.get(self.url("/rate_limit")).await?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_builder, mock_client};
+
+    #[tokio::test]
+    async fn records_rate_limit_headers() {
+        let _mock = mockito::mock("GET", "/rate_limit")
+            .with_header("x-ratelimit-limit", "5000")
+            .with_header("x-ratelimit-remaining", "4999")
+            .with_header("x-ratelimit-used", "1")
+            .with_header("x-ratelimit-reset", "1372700873")
+            .with_header("x-ratelimit-resource", "core")
+            .with_body(
+                r#"{"resources": {
+                    "core": {"limit": 5000, "remaining": 4999, "reset": 1372700873, "used": 1},
+                    "search": {"limit": 30, "remaining": 18, "reset": 1372697452, "used": 12}
+                  },
+                  "rate": {"limit": 5000, "remaining": 4999, "reset": 1372700873, "used": 1}}"#,
+            )
+            .create();
+
+        let octokit = mock_client();
+        let status = octokit.rate_limit().await.unwrap();
+
+        assert_eq!(status.resources.search.remaining, 18);
+        let last = octokit.last_rate_limit("core").unwrap();
+        assert_eq!(last.remaining, 4999);
+        assert_eq!(last.resource.as_deref(), Some("core"));
+    }
+
+    #[tokio::test]
+    async fn tracks_rate_limit_buckets_separately() {
+        let _search = mockito::mock("GET", "/repos/octocat/search-bucket/pulls")
+            .with_header("x-ratelimit-limit", "30")
+            .with_header("x-ratelimit-remaining", "29")
+            .with_header("x-ratelimit-reset", "1372697452")
+            .with_header("x-ratelimit-resource", "search")
+            .with_body("[]")
+            .create();
+        let _core = mockito::mock("GET", "/repos/octocat/buckets/pulls")
+            .with_header("x-ratelimit-limit", "5000")
+            .with_header("x-ratelimit-remaining", "4998")
+            .with_header("x-ratelimit-reset", "1372700873")
+            .with_body("[]")
+            .create();
+
+        let octokit = mock_client();
+        for repo in &["octocat/search-bucket", "octocat/buckets"] {
+            assert!(octokit.get_pull_requests(repo).next_item().await.is_none());
+        }
+
+        assert_eq!(octokit.last_rate_limit("search").unwrap().remaining, 29);
+        assert_eq!(octokit.last_rate_limit("core").unwrap().remaining, 4998);
+        assert_eq!(octokit.last_rate_limit("graphql"), None);
+    }
+
+    #[tokio::test]
+    async fn waits_out_secondary_rate_limits() {
+        let limited = mockito::mock("GET", "/repos/octocat/secondary/pulls")
+            .with_status(403)
+            .with_header("retry-after", "0")
+            .with_body(r#"{"message": "You have exceeded a secondary rate limit."}"#)
+            .expect(1)
+            .create();
+        let ok = mockito::mock("GET", "/repos/octocat/secondary/pulls")
+            .with_body("[]")
+            .expect(1)
+            .create();
+
+        let octokit = mock_builder()
+            .rate_limit_policy(RateLimitPolicy::Wait {
+                max_wait: Duration::from_secs(5),
+            })
+            .build()
+            .unwrap();
+
+        assert!(octokit
+            .get_pull_requests("octocat/secondary")
+            .next_item()
+            .await
+            .is_none());
+        limited.assert();
+        ok.assert();
+    }
+
+    #[test]
+    fn fail_policy_never_waits() {
+        let err = OctokitError::RateLimited {
+            status: reqwest::StatusCode::FORBIDDEN,
+            rate_limit: None,
+            retry_after: Some(Duration::from_secs(1)),
+            error: crate::ApiError {
+                message: "limited".to_string(),
+                documentation_url: None,
+                errors: Vec::new(),
+            },
+        };
+        assert_eq!(RateLimitPolicy::Fail.delay_for(&err), None);
+        assert_eq!(
+            RateLimitPolicy::Wait {
+                max_wait: Duration::from_millis(500)
+            }
+            .delay_for(&err),
+            None
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use crate::client::{decode, Octokit};
+use crate::error::OctokitError;
+use crate::{ReleaseAsset, ID};
+
+impl Octokit {
+    /// POST /repos/:owner/:repo/releases/:release_id/assets?name=:name
+    ///
+    /// Assets are uploaded to the upload URL of the client, not the API base URL.
+    pub async fn upload_release_asset(
+        &self,
+        nwo: &str,
+        release_id: ID,
+        name: &str,
+        content_type: &str,
+        data: Vec<u8>,
+    ) -> Result<ReleaseAsset, OctokitError> {
+        let mut url = reqwest::Url::parse(
+            &self.upload(&format!("/repos/{}/releases/{}/assets", nwo, release_id)),
+        )
+        .map_err(|err| OctokitError::new(&err.to_string()))?;
+        url.query_pairs_mut().append_pair("name", name);
+
+        decode(self.post_bytes(url.into(), content_type, data).await?).await
+    }
+}
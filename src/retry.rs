@@ -0,0 +1,229 @@
+use rand::Rng;
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+use crate::error::OctokitError;
+
+/// When and how often the client retries requests that failed for transient reasons.
+///
+/// Failed connections and `500`, `502`, `503` and `504` responses are retried
+/// with exponential backoff and full jitter. Only idempotent requests are
+/// retried by default, since a `POST` that timed out may still have been
+/// processed by GitHub; see [`retry_non_idempotent`](#method.retry_non_idempotent).
+///
+/// ```
+/// use octokit::retry::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::default()
+///     .max_attempts(5)
+///     .base_delay(Duration::from_secs(1))
+///     .retry_non_idempotent(true);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    statuses: Vec<StatusCode>,
+    non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            statuses: vec![
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that sends every request exactly once.
+    pub fn never() -> RetryPolicy {
+        RetryPolicy::default().max_attempts(1)
+    }
+
+    /// Total number of attempts per request, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled for every further attempt.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Upper bound for the delay between two attempts.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Replaces the response statuses that are considered transient.
+    pub fn statuses(mut self, statuses: Vec<StatusCode>) -> Self {
+        self.statuses = statuses;
+        self
+    }
+
+    /// Also retry `POST` and `PATCH` requests. Only enable this for endpoints
+    /// where a duplicate request is harmless.
+    pub fn retry_non_idempotent(mut self, enabled: bool) -> Self {
+        self.non_idempotent = enabled;
+        self
+    }
+
+    /// How long to wait before the next attempt, `None` to give up.
+    ///
+    /// `attempt` is the number of the attempt that just failed, starting at 1.
+    pub(crate) fn delay_for(
+        &self,
+        method: &Method,
+        err: &OctokitError,
+        attempt: u32,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !(self.non_idempotent || is_idempotent(method)) {
+            return None;
+        }
+        let transient = match err {
+            OctokitError::Transport(err) => !(err.is_builder() || err.is_redirect()),
+            OctokitError::Http { status, .. } => self.statuses.contains(status),
+            _ => false,
+        };
+        if transient {
+            Some(self.backoff(attempt))
+        } else {
+            None
+        }
+    }
+
+    /// Exponential backoff with full jitter, see
+    /// https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .checked_mul(2u32.saturating_pow(attempt - 1))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let millis = exponential.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::RateLimitPolicy;
+    use crate::testing::mock_builder;
+    use crate::ApiError;
+
+    fn bad_gateway() -> OctokitError {
+        OctokitError::Http {
+            status: StatusCode::BAD_GATEWAY,
+            error: ApiError {
+                message: "Bad Gateway".to_string(),
+                documentation_url: None,
+                errors: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn retries_only_idempotent_requests_by_default() {
+        let policy = RetryPolicy::default();
+        assert!(policy.delay_for(&Method::GET, &bad_gateway(), 1).is_some());
+        assert!(policy
+            .delay_for(&Method::DELETE, &bad_gateway(), 2)
+            .is_some());
+        assert!(policy.delay_for(&Method::GET, &bad_gateway(), 3).is_none());
+        assert!(policy.delay_for(&Method::POST, &bad_gateway(), 1).is_none());
+
+        let policy = policy.retry_non_idempotent(true);
+        assert!(policy.delay_for(&Method::POST, &bad_gateway(), 1).is_some());
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::default()
+            .max_attempts(100)
+            .max_delay(Duration::from_secs(2));
+        for attempt in 1..50 {
+            assert!(policy.backoff(attempt) <= Duration::from_secs(2));
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let mock = mockito::mock("GET", "/app/installations/17")
+            .with_status(503)
+            .expect(3)
+            .create();
+
+        let octokit = mock_builder()
+            .retry_policy(
+                RetryPolicy::default()
+                    .max_attempts(3)
+                    .base_delay(Duration::from_millis(10)),
+            )
+            .build()
+            .unwrap();
+        let err = octokit.get_installation(17).await.unwrap_err();
+
+        assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn rate_limit_waits_do_not_use_up_attempts() {
+        let limited = mockito::mock("GET", "/app/installations/18")
+            .with_status(403)
+            .with_header("retry-after", "0")
+            .with_body(r#"{"message": "You have exceeded a secondary rate limit."}"#)
+            .expect(1)
+            .create();
+        let failed = mockito::mock("GET", "/app/installations/18")
+            .with_status(502)
+            .expect(1)
+            .create();
+        let missing = mockito::mock("GET", "/app/installations/18")
+            .with_status(404)
+            .with_body(r#"{"message": "Not Found"}"#)
+            .expect(1)
+            .create();
+
+        let octokit = mock_builder()
+            .rate_limit_policy(RateLimitPolicy::Wait {
+                max_wait: Duration::from_secs(1),
+            })
+            .retry_policy(
+                RetryPolicy::default()
+                    .max_attempts(2)
+                    .base_delay(Duration::from_millis(10)),
+            )
+            .build()
+            .unwrap();
+        let err = octokit.get_installation(18).await.unwrap_err();
+
+        assert!(err.is_not_found());
+        limited.assert();
+        failed.assert();
+        missing.assert();
+    }
+}
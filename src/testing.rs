@@ -0,0 +1,17 @@
+//! Helpers shared by the unit tests.
+//!
+//! When several mocks match a request, mockito answers with the first one
+//! that has been hit less often than it `expect`s. Successive responses to
+//! the same request are mocked with one mock per response, created in order.
+
+use crate::{Octokit, OctokitBuilder};
+
+/// A client builder that sends every request to the mockito server.
+pub(crate) fn mock_builder() -> OctokitBuilder {
+    Octokit::builder().base_url(mockito::server_url())
+}
+
+/// A client for the mockito server that authenticates with the token `secret`.
+pub(crate) fn mock_client() -> Octokit {
+    mock_builder().token("secret").build().unwrap()
+}
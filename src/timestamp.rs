@@ -0,0 +1,204 @@
+//! Serde helpers for timestamps, which GitHub sends as ISO 8601 strings in
+//! most places but as Unix epoch seconds in some webhook payloads, e.g. for
+//! installations. Timestamps are serialized as ISO 8601 strings.
+//!
+//! ```text
+//! #[derive(Serialize, Deserialize)]
+//! struct Example {
+//!     #[serde(with = "crate::timestamp")]
+//!     created_at: DateTime,
+//!     #[serde(default, with = "crate::timestamp::option")]
+//!     closed_at: Option<DateTime>,
+//! }
+//! ```
+
+use chrono::{TimeZone, Utc};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::DateTime;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Timestamp {
+    Iso(String),
+    Epoch(i64),
+}
+
+impl Timestamp {
+    fn into_date_time<E: de::Error>(self) -> Result<DateTime, E> {
+        match self {
+            Timestamp::Iso(text) => chrono::DateTime::parse_from_rfc3339(&text)
+                .map(|date_time| date_time.with_timezone(&Utc))
+                .map_err(|err| E::custom(format!("invalid timestamp {}: {}", text, err))),
+            Timestamp::Epoch(secs) => Utc
+                .timestamp_opt(secs, 0)
+                .single()
+                .ok_or_else(|| E::custom(format!("invalid timestamp {}", secs))),
+        }
+    }
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime, D::Error> {
+    Timestamp::deserialize(deserializer)?.into_date_time()
+}
+
+pub(crate) fn serialize<S: Serializer>(
+    date_time: &DateTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    date_time.serialize(serializer)
+}
+
+pub(crate) mod option {
+    use super::*;
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime>, D::Error> {
+        Option::<Timestamp>::deserialize(deserializer)?
+            .map(Timestamp::into_date_time)
+            .transpose()
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        date_time: &Option<DateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        date_time.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        CheckRun, GithubApp, Hook, HookDelivery, Installation, InstallationToken, Release,
+        ReleaseAsset, ReviewComment, WorkflowJob, WorkflowRun,
+    };
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use serde_json::{json, Value};
+
+    const INSTALLATION: &str = r#"{"id": 1, "repository_selection": "all",
+        "access_tokens_url": "https://api.github.com/app/installations/1/access_tokens",
+        "repositories_url": "https://api.github.com/installation/repositories",
+        "html_url": "https://github.com/settings/installations/1", "app_id": 1,
+        "target_id": 1, "target_type": "Organization", "permissions": {}, "events": [],
+        "single_file_name": null"#;
+
+    fn installation(timestamps: &str) -> Installation {
+        serde_json::from_str(&format!("{}, {}}}", INSTALLATION, timestamps)).unwrap()
+    }
+
+    #[test]
+    fn accepts_iso_strings_and_epoch_seconds() {
+        let iso = installation(
+            r#""created_at": "2018-09-21T12:00:00Z", "updated_at": "2018-09-21T14:00:00+02:00""#,
+        );
+        let epoch = installation(r#""created_at": 1537531200, "updated_at": 1537531200"#);
+
+        assert_eq!(iso.created_at, epoch.created_at);
+        assert_eq!(iso.updated_at, epoch.updated_at);
+        assert_eq!(
+            serde_json::to_value(&epoch).unwrap()["created_at"],
+            "2018-09-21T12:00:00Z"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_timestamps() {
+        let result: Result<Installation, _> = serde_json::from_str(&format!(
+            r#"{}, "created_at": "yesterday", "updated_at": 0}}"#,
+            INSTALLATION
+        ));
+        assert!(result.is_err());
+    }
+
+    /// Decodes `json` with `fields` set to epoch seconds and checks that the
+    /// model encodes them as ISO strings, which decode to the same model.
+    fn round_trip<T: Serialize + DeserializeOwned>(mut json: Value, fields: &[&str]) {
+        for field in fields {
+            json[field] = json!(1537531200);
+        }
+        let model: T = serde_json::from_value(json).unwrap();
+        let encoded = serde_json::to_value(&model).unwrap();
+        for field in fields {
+            assert_eq!(encoded[field], "2018-09-21T12:00:00Z", "{}", field);
+        }
+        let decoded: T = serde_json::from_value(encoded.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), encoded);
+    }
+
+    #[test]
+    fn round_trips_epoch_seconds_through_every_model() {
+        let user = json!({"id": 1, "login": "octocat", "node_id": "MDQ6VXNlcjE=",
+            "type": "User", "site_admin": false});
+        let url = "https://api.github.com/";
+
+        round_trip::<GithubApp>(
+            json!({"id": 1, "slug": "app", "node_id": "MDM6QXBwMQ==", "owner": user,
+                "name": "app", "description": null, "external_url": url, "html_url": url,
+                "permissions": {"checks": "write"}, "events": [], "installations_count": null}),
+            &["created_at", "updated_at"],
+        );
+        round_trip::<Installation>(
+            json!({"id": 1, "repository_selection": "all", "access_tokens_url": url,
+                "repositories_url": url, "html_url": url, "app_id": 1, "target_id": 1,
+                "target_type": "Organization", "permissions": {}, "events": [],
+                "single_file_name": null}),
+            &["created_at", "updated_at"],
+        );
+        round_trip::<ReviewComment>(
+            json!({"id": 1, "body": "nit", "user": user}),
+            &["created_at", "updated_at"],
+        );
+        let asset = json!({"id": 1, "node_id": "MDEyOlJlbGVhc2VBc3NldDE=", "name": "a.zip",
+            "label": null, "content_type": "application/zip", "state": "uploaded",
+            "size": 1, "download_count": 0, "url": url, "browser_download_url": url,
+            "uploader": user, "created_at": 1537531200, "updated_at": 1537531200});
+        round_trip::<ReleaseAsset>(asset.clone(), &["created_at", "updated_at"]);
+        let release = json!({"id": 1, "node_id": "MDc6UmVsZWFzZTE=", "tag_name": "v1.0.0",
+            "target_commitish": "master", "name": null, "body": null, "draft": false,
+            "prerelease": false, "author": user, "assets": [asset], "url": url,
+            "html_url": url});
+        round_trip::<Release>(release.clone(), &["created_at", "published_at"]);
+        round_trip::<Release>(release, &["created_at"]);
+        round_trip::<WorkflowRun>(
+            json!({"id": 1, "name": "CI", "workflow_id": 1, "run_number": 1,
+                "event": "push", "head_branch": "master", "head_sha": "6dcb09b",
+                "status": "completed", "conclusion": "success", "url": url, "html_url": url}),
+            &["created_at", "updated_at"],
+        );
+        round_trip::<WorkflowJob>(
+            json!({"id": 1, "run_id": 1, "name": "test", "head_sha": "6dcb09b",
+                "status": "completed", "conclusion": "success", "url": url, "html_url": url}),
+            &["started_at", "completed_at"],
+        );
+        round_trip::<InstallationToken>(
+            json!({"token": "ghs_secret", "permissions": {}, "repository_selection": "all",
+                "repositories": null}),
+            &["expires_at"],
+        );
+        round_trip::<CheckRun>(
+            json!({"id": 1, "head_sha": "6dcb09b", "node_id": "MDg6Q2hlY2tSdW4x",
+                "external_id": "", "url": url, "html_url": url, "details_url": null,
+                "status": "completed", "conclusion": "success", "pull_requests": []}),
+            &["started_at", "completed_at"],
+        );
+        round_trip::<HookDelivery>(
+            json!({"id": 1, "guid": "72d3162e", "redelivery": false, "duration": 0.27,
+                "status": "OK", "status_code": 200, "event": "push", "action": null,
+                "installation_id": null, "repository_id": 1, "url": url,
+                "request": null, "response": null}),
+            &["delivered_at"],
+        );
+        round_trip::<Hook>(
+            json!({"id": 1, "type": "Repository", "name": "web", "active": true,
+                "events": ["push"], "config": {"url": url}, "url": url, "test_url": url,
+                "ping_url": url, "deliveries_url": url, "last_response": null}),
+            &["created_at", "updated_at"],
+        );
+    }
+}
@@ -1,50 +1,317 @@
 use crate::error::OctokitError;
+use log::{debug, warn};
 use openssl::hash::MessageDigest;
 use openssl::memcmp;
 use openssl::sign::Signer;
+use std::fmt;
+use std::time::SystemTime;
+
+pub mod delivery;
+pub mod events;
+pub mod router;
+#[cfg(feature = "server")]
+pub mod server;
+
+pub use events::WebhookEvent;
+pub use router::{WebhookContext, WebhookOutcome, WebhookRouter};
 
 pub const EVENT_HEADER_NAME: &str = "X-GITHUB-EVENT";
+/// Unique id of a delivery, kept when it is redelivered.
+pub const DELIVERY_HEADER_NAME: &str = "X-GITHUB-DELIVERY";
+/// HMAC-SHA1 signature, only kept for compatibility by GitHub.
 pub const SIGNATURE_HEADER_NAME: &str = "X-HUB-SIGNATURE";
+/// HMAC-SHA256 signature, preferred over the SHA1 one.
+pub const SIGNATURE_256_HEADER_NAME: &str = "X-HUB-SIGNATURE-256";
 
-pub fn verify_payload_signature(
-    signature: &Option<String>,
-    secret: &String,
-    body: &String,
-) -> bool {
-    match signature {
-        None => false,
-        Some(sig) => {
-            let result = verify(&sig, &secret, &body);
-            match result {
-                Ok(validity) => {
-                    println!("signature verification resulted in: {}", validity);
-                    validity
-                }
-                Err(err) => {
-                    println!("payload verification failed with: {}", err);
-                    false
-                }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignatureAlgorithm {
+    Sha1,
+    Sha256,
+}
+
+impl SignatureAlgorithm {
+    fn digest(self) -> MessageDigest {
+        match self {
+            SignatureAlgorithm::Sha1 => MessageDigest::sha1(),
+            SignatureAlgorithm::Sha256 => MessageDigest::sha256(),
+        }
+    }
+}
+
+/// Why a delivery failed signature verification.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureError {
+    /// The delivery carried neither signature header.
+    Missing,
+    /// The header is not of the form `<algorithm>=<hex digest>`.
+    Malformed(String),
+    UnsupportedAlgorithm(String),
+    /// The signature does not match the body, e.g. because the secret differs.
+    Mismatch,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Missing => write!(f, "missing webhook signature"),
+            SignatureError::Malformed(header) => {
+                write!(f, "malformed webhook signature: {}", header)
+            }
+            SignatureError::UnsupportedAlgorithm(algorithm) => {
+                write!(f, "unsupported signature algorithm: {}", algorithm)
             }
+            SignatureError::Mismatch => write!(f, "webhook signature does not match"),
         }
     }
 }
 
-///  Example signature header
-///  "x-hub-signature": "sha1=4b4a1c9a70dc40caf22099fb2d62a283dedd4614"
-fn verify(signature: &String, secret: &String, body: &String) -> Result<bool, OctokitError> {
-    let secret = secret.as_bytes();
-    let body = body.as_bytes();
+/// A parsed signature header such as `sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub algorithm: SignatureAlgorithm,
+    pub digest: Vec<u8>,
+}
+
+impl Signature {
+    pub fn parse(header: &str) -> Result<Signature, SignatureError> {
+        let malformed = || SignatureError::Malformed(header.to_string());
+        let (algorithm, hex_digest) = header.trim().split_once('=').ok_or_else(malformed)?;
+        let (algorithm, length) = match algorithm {
+            "sha1" => (SignatureAlgorithm::Sha1, 20),
+            "sha256" => (SignatureAlgorithm::Sha256, 32),
+            other => return Err(SignatureError::UnsupportedAlgorithm(other.to_string())),
+        };
+        let digest = hex::decode(hex_digest).map_err(|_| malformed())?;
+        if digest.len() != length {
+            return Err(malformed());
+        }
+        Ok(Signature { algorithm, digest })
+    }
 
-    // discard the 'sha1='-prefix
-    let sighex = &signature[5..];
-    // decode sha1 has hex bytes
-    let sigbytes = hex::decode(sighex)?;
-    // Create a PKey
-    let key = openssl::pkey::PKey::hmac(secret)?;
-    // Compute the HMAC
-    let mut signer = Signer::new(MessageDigest::sha1(), &key)?;
-    signer.update(body)?;
-    let hmac = signer.sign_to_vec()?;
+    /// Checks the signature against `body`, in constant time.
+    pub fn verify(&self, secret: &[u8], body: &[u8]) -> Result<(), OctokitError> {
+        let key = openssl::pkey::PKey::hmac(secret)?;
+        let mut signer = Signer::new(self.algorithm.digest(), &key)?;
+        signer.update(body)?;
+        let hmac = signer.sign_to_vec()?;
+
+        if memcmp::eq(&hmac, &self.digest) {
+            Ok(())
+        } else {
+            Err(SignatureError::Mismatch.into())
+        }
+    }
+}
+
+/// Verifies a delivery given the values of the `X-Hub-Signature-256` and
+/// `X-Hub-Signature` headers. The SHA256 signature is used when both exist.
+pub fn verify_signature(
+    signature_256: Option<&str>,
+    signature: Option<&str>,
+    secret: &str,
+    body: &[u8],
+) -> Result<(), OctokitError> {
+    let header = signature_256.or(signature).ok_or(SignatureError::Missing)?;
+    Signature::parse(header)?.verify(secret.as_bytes(), body)
+}
+
+/// Verifies deliveries against a set of secrets, so a webhook secret can be
+/// rotated without rejecting deliveries signed with the previous one.
+///
+/// ```
+/// use octokit::webhooks::WebhookVerifier;
+/// use std::time::{Duration, SystemTime};
+///
+/// let verifier = WebhookVerifier::new()
+///     .secret("2024-06", "new secret")
+///     .retired_secret("2023-01", "old secret", SystemTime::now() + Duration::from_secs(3600));
+/// # let (signature_256, body) = (None, b"{}");
+/// match verifier.verify(signature_256, None, body) {
+///     Ok(secret) => println!("signed with {}", secret),
+///     Err(err) => println!("rejected: {}", err),
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct WebhookVerifier {
+    secrets: Vec<WebhookSecret>,
+}
+
+#[derive(Clone)]
+struct WebhookSecret {
+    id: String,
+    secret: Vec<u8>,
+    expires_at: Option<SystemTime>,
+}
+
+impl WebhookVerifier {
+    pub fn new() -> WebhookVerifier {
+        WebhookVerifier::default()
+    }
+
+    /// Accepts deliveries signed with `secret`. `id` names the secret in
+    /// the result of [`verify`](#method.verify), it is never compared.
+    pub fn secret(self, id: impl Into<String>, secret: impl Into<Vec<u8>>) -> Self {
+        self.add(id.into(), secret.into(), None)
+    }
+
+    /// Accepts deliveries signed with a retired `secret` until `expires_at`.
+    pub fn retired_secret(
+        self,
+        id: impl Into<String>,
+        secret: impl Into<Vec<u8>>,
+        expires_at: SystemTime,
+    ) -> Self {
+        self.add(id.into(), secret.into(), Some(expires_at))
+    }
+
+    fn add(mut self, id: String, secret: Vec<u8>, expires_at: Option<SystemTime>) -> Self {
+        self.secrets.push(WebhookSecret {
+            id,
+            secret,
+            expires_at,
+        });
+        self
+    }
 
-    Ok(memcmp::eq(&hmac, &sigbytes))
+    /// Verifies a delivery like [`verify_signature`](fn.verify_signature.html)
+    /// and returns the id of the secret it was signed with. Expired secrets
+    /// are skipped.
+    pub fn verify(
+        &self,
+        signature_256: Option<&str>,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<&str, OctokitError> {
+        let header = signature_256.or(signature).ok_or(SignatureError::Missing)?;
+        let signature = Signature::parse(header)?;
+        let now = SystemTime::now();
+        for secret in &self.secrets {
+            if secret
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= now)
+            {
+                continue;
+            }
+            match signature.verify(&secret.secret, body) {
+                Ok(()) => return Ok(&secret.id),
+                Err(OctokitError::Signature(SignatureError::Mismatch)) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(SignatureError::Mismatch.into())
+    }
+}
+
+impl fmt::Debug for WebhookVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print the secrets themselves
+        f.debug_list()
+            .entries(self.secrets.iter().map(|secret| &secret.id))
+            .finish()
+    }
+}
+
+/// Like [`verify_signature`](fn.verify_signature.html) for a single header
+/// of either algorithm, logging why verification failed.
+pub fn verify_payload_signature(signature: Option<&str>, secret: &str, body: &[u8]) -> bool {
+    match verify_signature(None, signature, secret, body) {
+        Ok(()) => {
+            debug!("signature verification succeeded");
+            true
+        }
+        Err(err) => {
+            warn!("payload verification failed with: {}", err);
+            false
+        }
+    }
+}
+
+/// The `X-Hub-Signature-256` header GitHub would send for `body`.
+#[cfg(test)]
+pub(crate) fn sign_sha256(secret: &str, body: &[u8]) -> String {
+    let key = openssl::pkey::PKey::hmac(secret.as_bytes()).unwrap();
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+    signer.update(body).unwrap();
+    format!("sha256={}", hex::encode(signer.sign_to_vec().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // example from https://docs.github.com/webhooks/using-webhooks/validating-webhook-deliveries
+    const SECRET: &str = "It's a Secret to Everybody";
+    const BODY: &[u8] = b"Hello, World!";
+    const SHA256: &str = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+    const SHA1: &str = "sha1=01dc10d0c83e72ed246219cdd91669667fe2ca59";
+
+    #[test]
+    fn verifies_both_algorithms() {
+        assert!(verify_signature(Some(SHA256), None, SECRET, BODY).is_ok());
+        assert!(verify_signature(None, Some(SHA1), SECRET, BODY).is_ok());
+        assert!(verify_payload_signature(Some(SHA1), SECRET, BODY));
+    }
+
+    #[test]
+    fn prefers_sha256() {
+        let wrong_sha1 = "sha1=0000000000000000000000000000000000000000";
+        assert!(verify_signature(Some(SHA256), Some(wrong_sha1), SECRET, BODY).is_ok());
+        assert!(verify_signature(Some(wrong_sha1), Some(SHA256), SECRET, BODY).is_err());
+    }
+
+    #[test]
+    fn verifies_non_utf8_bodies() {
+        let body = [0xff, 0xfe, 0x00];
+        let header = sign_sha256(SECRET, &body);
+
+        assert!(verify_signature(Some(&header), None, SECRET, &body).is_ok());
+    }
+
+    #[test]
+    fn reports_the_matching_secret() {
+        let hour = std::time::Duration::from_secs(3600);
+        let verifier = WebhookVerifier::new()
+            .secret("new", "rotated secret")
+            .retired_secret("old", SECRET, SystemTime::now() + hour);
+        assert_eq!(verifier.verify(Some(SHA256), None, BODY).unwrap(), "old");
+        assert_eq!(format!("{:?}", verifier), r#"["new", "old"]"#);
+
+        let expired = WebhookVerifier::new()
+            .secret("new", "rotated secret")
+            .retired_secret("old", SECRET, SystemTime::now() - hour);
+        assert!(matches!(
+            expired.verify(Some(SHA256), None, BODY),
+            Err(OctokitError::Signature(SignatureError::Mismatch))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        fn error(signature_256: Option<&str>) -> SignatureError {
+            match verify_signature(signature_256, None, SECRET, BODY) {
+                Err(OctokitError::Signature(err)) => err,
+                other => panic!("expected a signature error, got {:?}", other),
+            }
+        }
+
+        assert_eq!(error(None), SignatureError::Missing);
+        assert_eq!(
+            error(Some("sha")),
+            SignatureError::Malformed("sha".to_string())
+        );
+        assert_eq!(
+            error(Some("sha256=abc")),
+            SignatureError::Malformed("sha256=abc".to_string())
+        );
+        assert_eq!(
+            error(Some("md5=abc")),
+            SignatureError::UnsupportedAlgorithm("md5".to_string())
+        );
+        assert_eq!(
+            error(Some(
+                "sha256=0000000000000000000000000000000000000000000000000000000000000000"
+            )),
+            SignatureError::Mismatch
+        );
+    }
 }
@@ -0,0 +1,354 @@
+//! Remembers seen deliveries, so redelivered events are handled only once.
+//!
+//! GitHub keeps the `X-GitHub-Delivery` id when it redelivers an event,
+//! both automatically and when a delivery is redelivered by hand.
+//!
+//! ```no_run
+//! use octokit::webhooks::delivery::InMemoryDeliveryStore;
+//! use octokit::webhooks::{WebhookRouter, WebhookVerifier};
+//! use std::time::Duration;
+//!
+//! let router = WebhookRouter::new(WebhookVerifier::new().secret("default", "secret"))
+//!     .deliveries(InMemoryDeliveryStore::new(Duration::from_secs(24 * 3600)));
+//! ```
+
+use http::HeaderMap;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::router::header;
+use super::{WebhookEvent, DELIVERY_HEADER_NAME, EVENT_HEADER_NAME};
+use crate::error::OctokitError;
+
+/// The value of the `X-GitHub-Delivery` header, a GUID identifying a delivery.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DeliveryId(String);
+
+impl DeliveryId {
+    pub fn new(id: impl Into<String>) -> DeliveryId {
+        DeliveryId(id.into())
+    }
+
+    /// The id from the `X-GitHub-Delivery` header of a delivery.
+    pub fn from_headers(headers: &HeaderMap) -> Option<DeliveryId> {
+        header(headers, DELIVERY_HEADER_NAME).map(DeliveryId::from)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for DeliveryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for DeliveryId {
+    fn from(id: String) -> DeliveryId {
+        DeliveryId(id)
+    }
+}
+
+impl From<&str> for DeliveryId {
+    fn from(id: &str) -> DeliveryId {
+        DeliveryId(id.to_string())
+    }
+}
+
+/// A decoded delivery: the event along with the id GitHub keeps when it
+/// redelivers it.
+///
+/// ```
+/// use octokit::webhooks::delivery::WebhookDelivery;
+/// use octokit::webhooks::WebhookEvent;
+///
+/// let mut headers = http::HeaderMap::new();
+/// headers.insert("X-GitHub-Event", "ping".parse().unwrap());
+/// headers.insert("X-GitHub-Delivery", "72d3162e".parse().unwrap());
+/// let body = br#"{"zen": "Design for failure.", "hook_id": 1}"#;
+///
+/// let delivery = WebhookDelivery::parse(&headers, body)?;
+/// assert_eq!(delivery.id.unwrap().as_str(), "72d3162e");
+/// assert!(matches!(delivery.event, WebhookEvent::Ping(_)));
+/// # Ok::<(), octokit::error::OctokitError>(())
+/// ```
+#[derive(Debug)]
+pub struct WebhookDelivery {
+    /// The value of the `X-GitHub-Delivery` header, which GitHub always sends.
+    pub id: Option<DeliveryId>,
+    /// The value of the `X-GitHub-Event` header, also set for events this
+    /// crate does not know about.
+    pub event_name: String,
+    pub event: WebhookEvent,
+}
+
+impl WebhookDelivery {
+    /// Decodes a delivery from its headers and body. Does not check the
+    /// signature, verify it with a [`WebhookVerifier`](../struct.WebhookVerifier.html) first.
+    pub fn parse(headers: &HeaderMap, body: &[u8]) -> Result<WebhookDelivery, OctokitError> {
+        let event_name = header(headers, EVENT_HEADER_NAME)
+            .ok_or_else(|| OctokitError::new("missing X-GitHub-Event header"))?;
+        Ok(WebhookDelivery {
+            id: DeliveryId::from_headers(headers),
+            event_name: event_name.to_string(),
+            event: WebhookEvent::parse(event_name, body)?,
+        })
+    }
+}
+
+/// The delivery ids a [`WebhookRouter`](../router/struct.WebhookRouter.html)
+/// has accepted, consulted to skip redeliveries.
+///
+/// An id only counts as seen for the TTL of the store, counted from when it
+/// was recorded: GitHub redelivers within hours, so ids do not have to be
+/// kept forever, but a delivery redelivered after the TTL is handled again.
+///
+/// Ids are recorded when a delivery is accepted, before its handlers run, so
+/// a redelivery arriving while they still run is skipped. When a handler
+/// fails, the router calls [`remove`](#tymethod.remove) so that redelivering
+/// the failed delivery runs the handlers again instead of being skipped.
+pub trait DeliveryStore: Send + Sync {
+    /// Records a delivery, returning `false` if it was recorded within the
+    /// TTL. Has to check and record atomically, or concurrent redeliveries
+    /// slip through.
+    fn insert(&self, id: &DeliveryId) -> bool;
+
+    /// Forgets a delivery whose handlers failed, so it is handled again when
+    /// it is redelivered.
+    fn remove(&self, id: &DeliveryId);
+}
+
+/// Keeps delivery ids in memory for `ttl`.
+pub struct InMemoryDeliveryStore {
+    ttl: Duration,
+    seen: Mutex<HashMap<DeliveryId, SystemTime>>,
+}
+
+impl InMemoryDeliveryStore {
+    pub fn new(ttl: Duration) -> InMemoryDeliveryStore {
+        InMemoryDeliveryStore {
+            ttl,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.lock().map_or(0, |seen| seen.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl DeliveryStore for InMemoryDeliveryStore {
+    fn insert(&self, id: &DeliveryId) -> bool {
+        let mut seen = match self.seen.lock() {
+            Ok(seen) => seen,
+            // rather handle a delivery twice than not at all
+            Err(_) => return true,
+        };
+        insert(&mut seen, id, self.ttl)
+    }
+
+    fn remove(&self, id: &DeliveryId) {
+        if let Ok(mut seen) = self.seen.lock() {
+            seen.remove(id);
+        }
+    }
+}
+
+/// Rewrite the file of a [`FileDeliveryStore`] once it holds this many
+/// lines of expired or forgotten deliveries.
+const COMPACT_AFTER: usize = 1000;
+
+/// Keeps delivery ids for `ttl` in a file, so they survive restarts.
+///
+/// Every delivery is appended as a line with its id and the time it was
+/// seen. Expired lines are dropped when the store is opened and whenever
+/// enough of them piled up.
+pub struct FileDeliveryStore {
+    path: PathBuf,
+    ttl: Duration,
+    state: Mutex<FileState>,
+}
+
+struct FileState {
+    seen: HashMap<DeliveryId, SystemTime>,
+    /// Number of lines in the file, including expired deliveries.
+    lines: usize,
+}
+
+impl FileDeliveryStore {
+    /// Uses the file at `path`, creating it if necessary.
+    pub fn open(path: impl Into<PathBuf>, ttl: Duration) -> std::io::Result<FileDeliveryStore> {
+        let path = path.into();
+        let now = SystemTime::now();
+        let seen = match fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .lines()
+                .filter_map(parse_line)
+                .filter(|(_, seen_at)| !expired(*seen_at, ttl, now))
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err),
+        };
+        write_all(&path, &seen)?;
+        Ok(FileDeliveryStore {
+            path,
+            ttl,
+            state: Mutex::new(FileState {
+                lines: seen.len(),
+                seen,
+            }),
+        })
+    }
+}
+
+impl DeliveryStore for FileDeliveryStore {
+    fn insert(&self, id: &DeliveryId) -> bool {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return true,
+        };
+        if !insert(&mut state.seen, id, self.ttl) {
+            return false;
+        }
+        // every live delivery has a line, the one just recorded is appended below
+        let stale = state.lines + 1 - state.seen.len();
+        let result = if stale >= COMPACT_AFTER {
+            state.lines = state.seen.len();
+            write_all(&self.path, &state.seen)
+        } else {
+            state.lines += 1;
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .and_then(|mut file| file.write_all(format_line(id, state.seen[id]).as_bytes()))
+        };
+        if let Err(err) = result {
+            warn!("failed to record delivery {}: {}", id, err);
+        }
+        true
+    }
+
+    fn remove(&self, id: &DeliveryId) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        if state.seen.remove(id).is_some() {
+            state.lines = state.seen.len();
+            if let Err(err) = write_all(&self.path, &state.seen) {
+                warn!("failed to forget delivery {}: {}", id, err);
+            }
+        }
+    }
+}
+
+/// Records `id` unless it was seen within `ttl`, pruning expired ids.
+fn insert(seen: &mut HashMap<DeliveryId, SystemTime>, id: &DeliveryId, ttl: Duration) -> bool {
+    let now = SystemTime::now();
+    seen.retain(|_, seen_at| !expired(*seen_at, ttl, now));
+    if seen.contains_key(id) {
+        return false;
+    }
+    seen.insert(id.clone(), now);
+    true
+}
+
+fn expired(seen_at: SystemTime, ttl: Duration, now: SystemTime) -> bool {
+    now.duration_since(seen_at).is_ok_and(|age| age >= ttl)
+}
+
+fn format_line(id: &DeliveryId, seen_at: SystemTime) -> String {
+    let secs = seen_at
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    format!("{} {}\n", id, secs)
+}
+
+fn parse_line(line: &str) -> Option<(DeliveryId, SystemTime)> {
+    let (id, secs) = line.split_once(' ')?;
+    let secs = secs.trim().parse().ok()?;
+    Some((DeliveryId::from(id), UNIX_EPOCH + Duration::from_secs(secs)))
+}
+
+fn write_all(path: &Path, seen: &HashMap<DeliveryId, SystemTime>) -> std::io::Result<()> {
+    let contents: String = seen
+        .iter()
+        .map(|(id, seen_at)| format_line(id, *seen_at))
+        .collect();
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_seen_deliveries() {
+        let store = InMemoryDeliveryStore::new(Duration::from_secs(60));
+        let id = DeliveryId::from("72d3162e-cc78-11e3-81ab-4c9367dc0958");
+
+        assert!(store.insert(&id));
+        assert!(!store.insert(&id));
+        store.remove(&id);
+        assert!(store.insert(&id));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn forgets_deliveries_after_the_ttl() {
+        let store = InMemoryDeliveryStore::new(Duration::from_secs(0));
+        let id = DeliveryId::from("72d3162e");
+
+        assert!(store.insert(&id));
+        assert!(store.insert(&id));
+    }
+
+    #[test]
+    fn persists_deliveries_in_a_file() {
+        let path = std::env::temp_dir().join(format!("octokit-deliveries-{}", std::process::id()));
+        let hour = Duration::from_secs(3600);
+        let (kept, removed) = (DeliveryId::from("kept"), DeliveryId::from("removed"));
+        {
+            let store = FileDeliveryStore::open(&path, hour).unwrap();
+            assert!(store.insert(&kept));
+            assert!(store.insert(&removed));
+            store.remove(&removed);
+        }
+
+        let reopened = FileDeliveryStore::open(&path, hour).unwrap();
+        assert!(!reopened.insert(&kept));
+        assert!(reopened.insert(&removed));
+
+        let expired = FileDeliveryStore::open(&path, Duration::from_secs(0)).unwrap();
+        assert!(expired.insert(&kept));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn compacts_the_file_while_in_use() {
+        let path = std::env::temp_dir().join(format!("octokit-compaction-{}", std::process::id()));
+        let store = FileDeliveryStore::open(&path, Duration::from_secs(0)).unwrap();
+
+        for n in 0..=COMPACT_AFTER {
+            assert!(store.insert(&DeliveryId::new(n.to_string())));
+        }
+
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert!(lines < COMPACT_AFTER, "{} lines left", lines);
+        fs::remove_file(path).unwrap();
+    }
+}
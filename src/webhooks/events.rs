@@ -0,0 +1,834 @@
+//! Typed webhook payloads, dispatched by the `X-GitHub-Event` header.
+//!
+//! ```
+//! use octokit::webhooks::events::WebhookEvent;
+//!
+//! # let body = br#"{"zen": "Design for failure.", "hook_id": 1}"#;
+//! match WebhookEvent::parse("ping", body)? {
+//!     WebhookEvent::Ping(ping) => println!("{}", ping.zen),
+//!     WebhookEvent::Unknown(payload) => println!("unsupported event: {}", payload),
+//!     _ => {}
+//! }
+//! # Ok::<(), octokit::error::OctokitError>(())
+//! ```
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::fmt;
+
+use super::router::{EventFilter, WebhookContext};
+use crate::error::OctokitError;
+use crate::{
+    CheckRun, CheckSuite, CommitSha, DateTime, Hook, Installation, Issue, IssueComment,
+    PullRequest, Release, Repository, ReviewComment, User, WorkflowJob, WorkflowRun, GRID, ID, URI,
+};
+
+type Email = String;
+
+/// Generates the event enum along with the mapping from event names to variants.
+macro_rules! webhook_events {
+    ($($(#[$doc:meta])* $name:literal => $variant:ident($payload:ty),)*) => {
+        /// A webhook delivery, one variant per `X-GitHub-Event` value.
+        ///
+        /// Payloads of events without a dedicated model are kept as
+        /// [`EventPayload`](struct.EventPayload.html), events this crate does
+        /// not know about end up in `Unknown`.
+        #[derive(Debug)]
+        pub enum WebhookEvent {
+            $($(#[$doc])* $variant(Box<$payload>),)*
+            Unknown(Value),
+        }
+
+        impl WebhookEvent {
+            /// Decodes `body` according to `event_name`, the value of the
+            /// `X-GitHub-Event` header.
+            pub fn parse(event_name: &str, body: &[u8]) -> Result<WebhookEvent, OctokitError> {
+                match event_name {
+                    $($name => decode(body).map(WebhookEvent::$variant),)*
+                    _ => decode(body).map(WebhookEvent::Unknown),
+                }
+            }
+
+            /// The `X-GitHub-Event` value of the event, `None` for unknown events.
+            pub fn event_name(&self) -> Option<&'static str> {
+                match self {
+                    $(WebhookEvent::$variant(_) => Some($name),)*
+                    WebhookEvent::Unknown(_) => None,
+                }
+            }
+
+            /// The `action` of the payload, `None` for events without actions.
+            pub fn action(&self) -> Option<&str> {
+                match self {
+                    $(WebhookEvent::$variant(payload) => payload.action(),)*
+                    WebhookEvent::Unknown(payload) => payload.get("action").and_then(Value::as_str),
+                }
+            }
+
+            /// The id of the installation the event was delivered for, set for
+            /// GitHub App webhooks.
+            pub fn installation_id(&self) -> Option<ID> {
+                match self {
+                    $(WebhookEvent::$variant(payload) => payload.installation_id(),)*
+                    WebhookEvent::Unknown(payload) => {
+                        payload.pointer("/installation/id").and_then(Value::as_u64)
+                    }
+                }
+            }
+        }
+    };
+}
+
+webhook_events! {
+    "branch_protection_rule" => BranchProtectionRule(EventPayload),
+    "check_run" => CheckRun(CheckRunPayload),
+    "check_suite" => CheckSuite(CheckSuitePayload),
+    "code_scanning_alert" => CodeScanningAlert(EventPayload),
+    "commit_comment" => CommitComment(EventPayload),
+    "create" => Create(CreatePayload),
+    "delete" => Delete(DeletePayload),
+    "deploy_key" => DeployKey(EventPayload),
+    "deployment" => Deployment(EventPayload),
+    "deployment_status" => DeploymentStatus(EventPayload),
+    "discussion" => Discussion(EventPayload),
+    "discussion_comment" => DiscussionComment(EventPayload),
+    "fork" => Fork(EventPayload),
+    "github_app_authorization" => GithubAppAuthorization(EventPayload),
+    "gollum" => Gollum(EventPayload),
+    "installation" => Installation(InstallationPayload),
+    "installation_repositories" => InstallationRepositories(InstallationRepositoriesPayload),
+    "installation_target" => InstallationTarget(EventPayload),
+    "issue_comment" => IssueComment(IssueCommentPayload),
+    "issues" => Issues(IssuesPayload),
+    "label" => Label(EventPayload),
+    "marketplace_purchase" => MarketplacePurchase(EventPayload),
+    "member" => Member(EventPayload),
+    "membership" => Membership(EventPayload),
+    "merge_group" => MergeGroup(EventPayload),
+    "meta" => Meta(EventPayload),
+    "milestone" => Milestone(EventPayload),
+    "org_block" => OrgBlock(EventPayload),
+    "organization" => Organization(EventPayload),
+    "package" => Package(EventPayload),
+    "page_build" => PageBuild(EventPayload),
+    /// Sent once when a webhook is created.
+    "ping" => Ping(PingPayload),
+    "project" => Project(EventPayload),
+    "project_card" => ProjectCard(EventPayload),
+    "project_column" => ProjectColumn(EventPayload),
+    "public" => Public(EventPayload),
+    "pull_request" => PullRequest(PullRequestPayload),
+    "pull_request_review" => PullRequestReview(EventPayload),
+    "pull_request_review_comment" => PullRequestReviewComment(PullRequestReviewCommentPayload),
+    "pull_request_review_thread" => PullRequestReviewThread(EventPayload),
+    "push" => Push(PushPayload),
+    "registry_package" => RegistryPackage(EventPayload),
+    "release" => Release(ReleasePayload),
+    "repository" => Repository(EventPayload),
+    "repository_dispatch" => RepositoryDispatch(EventPayload),
+    "repository_import" => RepositoryImport(EventPayload),
+    "repository_vulnerability_alert" => RepositoryVulnerabilityAlert(EventPayload),
+    "security_advisory" => SecurityAdvisory(EventPayload),
+    "sponsorship" => Sponsorship(EventPayload),
+    "star" => Star(EventPayload),
+    "status" => Status(StatusPayload),
+    "team" => Team(EventPayload),
+    "team_add" => TeamAdd(EventPayload),
+    "watch" => Watch(EventPayload),
+    "workflow_dispatch" => WorkflowDispatch(EventPayload),
+    "workflow_job" => WorkflowJob(WorkflowJobPayload),
+    "workflow_run" => WorkflowRun(WorkflowRunPayload),
+}
+
+/// Generates an action enum per event. Actions GitHub adds later end up in
+/// `Other`, so matching on them never fails to compile or to deserialize.
+macro_rules! event_actions {
+    ($($(#[$doc:meta])* $name:ident for $event:literal { $($(#[$variant_doc:meta])* $variant:ident => $value:literal,)* })*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub enum $name {
+                $($(#[$variant_doc])* $variant,)*
+                /// An action this crate does not know about yet.
+                Other(String),
+            }
+
+            impl $name {
+                pub fn as_str(&self) -> &str {
+                    match self {
+                        $($name::$variant => $value,)*
+                        $name::Other(action) => action,
+                    }
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let action = String::deserialize(deserializer)?;
+                    Ok(match action.as_str() {
+                        $($value => $name::$variant,)*
+                        _ => $name::Other(action),
+                    })
+                }
+            }
+
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str(self.as_str())
+                }
+            }
+
+            impl EventFilter for $name {
+                fn accepts(&self, ctx: &WebhookContext) -> bool {
+                    ctx.event_name() == $event && ctx.event().action() == Some(self.as_str())
+                }
+            }
+        )*
+    };
+}
+
+event_actions! {
+    PullRequestAction for "pull_request" {
+        Assigned => "assigned",
+        AutoMergeDisabled => "auto_merge_disabled",
+        AutoMergeEnabled => "auto_merge_enabled",
+        Closed => "closed",
+        ConvertedToDraft => "converted_to_draft",
+        Demilestoned => "demilestoned",
+        Dequeued => "dequeued",
+        Edited => "edited",
+        Enqueued => "enqueued",
+        Labeled => "labeled",
+        Locked => "locked",
+        Milestoned => "milestoned",
+        Opened => "opened",
+        ReadyForReview => "ready_for_review",
+        Reopened => "reopened",
+        ReviewRequestRemoved => "review_request_removed",
+        ReviewRequested => "review_requested",
+        /// New commits were pushed to the head branch.
+        Synchronize => "synchronize",
+        Unassigned => "unassigned",
+        Unlabeled => "unlabeled",
+        Unlocked => "unlocked",
+    }
+
+    PullRequestReviewCommentAction for "pull_request_review_comment" {
+        Created => "created",
+        Edited => "edited",
+        Deleted => "deleted",
+    }
+
+    IssuesAction for "issues" {
+        Assigned => "assigned",
+        Closed => "closed",
+        Deleted => "deleted",
+        Demilestoned => "demilestoned",
+        Edited => "edited",
+        Labeled => "labeled",
+        Locked => "locked",
+        Milestoned => "milestoned",
+        Opened => "opened",
+        Pinned => "pinned",
+        Reopened => "reopened",
+        Transferred => "transferred",
+        Unassigned => "unassigned",
+        Unlabeled => "unlabeled",
+        Unlocked => "unlocked",
+        Unpinned => "unpinned",
+    }
+
+    IssueCommentAction for "issue_comment" {
+        Created => "created",
+        Edited => "edited",
+        Deleted => "deleted",
+    }
+
+    CheckSuiteAction for "check_suite" {
+        Completed => "completed",
+        Requested => "requested",
+        Rerequested => "rerequested",
+    }
+
+    CheckRunAction for "check_run" {
+        Created => "created",
+        Completed => "completed",
+        Rerequested => "rerequested",
+        /// A user clicked a button the app added to the check run.
+        RequestedAction => "requested_action",
+    }
+
+    InstallationAction for "installation" {
+        Created => "created",
+        Deleted => "deleted",
+        NewPermissionsAccepted => "new_permissions_accepted",
+        Suspend => "suspend",
+        Unsuspend => "unsuspend",
+    }
+
+    InstallationRepositoriesAction for "installation_repositories" {
+        Added => "added",
+        Removed => "removed",
+    }
+
+    ReleaseAction for "release" {
+        Created => "created",
+        Deleted => "deleted",
+        Edited => "edited",
+        Prereleased => "prereleased",
+        Published => "published",
+        Released => "released",
+        Unpublished => "unpublished",
+    }
+
+    WorkflowRunAction for "workflow_run" {
+        Completed => "completed",
+        InProgress => "in_progress",
+        Requested => "requested",
+    }
+
+    WorkflowJobAction for "workflow_job" {
+        Queued => "queued",
+        InProgress => "in_progress",
+        Completed => "completed",
+        Waiting => "waiting",
+    }
+}
+
+/// The fields the router dispatches on, which every payload has in some form.
+trait Payload {
+    fn action(&self) -> Option<&str>;
+    fn installation_id(&self) -> Option<ID>;
+}
+
+macro_rules! payload_fields {
+    ($($payload:ty => |$this:ident| ($action:expr, $installation_id:expr),)*) => {
+        $(
+            impl Payload for $payload {
+                fn action(&self) -> Option<&str> {
+                    #[allow(unused_variables)]
+                    let $this = self;
+                    $action
+                }
+
+                fn installation_id(&self) -> Option<ID> {
+                    #[allow(unused_variables)]
+                    let $this = self;
+                    $installation_id
+                }
+            }
+        )*
+    };
+}
+
+payload_fields! {
+    EventPayload => |p| (p.action.as_deref(), p.installation.as_ref().map(|i| i.id)),
+    PingPayload => |p| (None, None),
+    PushPayload => |p| (None, p.installation.as_ref().map(|i| i.id)),
+    PullRequestPayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    PullRequestReviewCommentPayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    IssuesPayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    IssueCommentPayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    CheckSuitePayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    CheckRunPayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    InstallationPayload => |p| (Some(p.action.as_str()), Some(p.installation.id)),
+    InstallationRepositoriesPayload => |p| (Some(p.action.as_str()), Some(p.installation.id)),
+    ReleasePayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    WorkflowRunPayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    WorkflowJobPayload => |p| (Some(p.action.as_str()), p.installation.as_ref().map(|i| i.id)),
+    CreatePayload => |p| (None, p.installation.as_ref().map(|i| i.id)),
+    DeletePayload => |p| (None, p.installation.as_ref().map(|i| i.id)),
+    StatusPayload => |p| (None, p.installation.as_ref().map(|i| i.id)),
+}
+
+fn decode<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, OctokitError> {
+    serde_json::from_slice(body).map_err(|source| OctokitError::Decode {
+        source,
+        body: String::from_utf8_lossy(body).into_owned(),
+    })
+}
+
+/// The installation an event was delivered for, set for GitHub App webhooks.
+#[derive(Deserialize, Debug)]
+pub struct EventInstallation {
+    pub id: ID,
+    pub node_id: Option<GRID>,
+}
+
+/// The payload of events without a dedicated model. The fields every event
+/// shares are decoded, everything else is kept as JSON.
+#[derive(Deserialize, Debug)]
+pub struct EventPayload {
+    pub action: Option<String>,
+    pub repository: Option<Repository>,
+    pub sender: Option<User>,
+    pub installation: Option<EventInstallation>,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PingPayload {
+    pub zen: String,
+    pub hook_id: ID,
+    pub hook: Option<Hook>,
+    pub repository: Option<Repository>,
+    pub sender: Option<User>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CommitAuthor {
+    pub name: String,
+    pub email: Email,
+}
+
+/// Used for web-flows etc, that are on behalf of a given user
+type Committer = CommitAuthor;
+
+#[derive(Deserialize, Debug)]
+pub struct Commit {
+    pub id: CommitSha,
+    pub tree_id: CommitSha,
+    pub distinct: bool,
+    pub message: String,
+    pub author: CommitAuthor,
+    pub committer: Committer,
+    pub url: URI,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Pusher {
+    pub name: String,
+    pub email: Option<Email>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PushPayload {
+    pub r#ref: String,
+    pub before: CommitSha,
+    pub after: CommitSha,
+    pub created: bool,
+    pub deleted: bool,
+    pub forced: bool,
+    pub base_ref: Option<String>,
+    pub compare: URI,
+    pub commits: Vec<Commit>,
+    pub head_commit: Option<Commit>,
+    pub repository: Repository,
+    pub pusher: Pusher,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PullRequestPayload {
+    pub action: PullRequestAction,
+    pub number: u64,
+    pub pull_request: PullRequest,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PullRequestReviewCommentPayload {
+    pub action: PullRequestReviewCommentAction,
+    pub comment: ReviewComment,
+    pub pull_request: PullRequest,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IssuesPayload {
+    pub action: IssuesAction,
+    pub issue: Issue,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IssueCommentPayload {
+    pub action: IssueCommentAction,
+    pub issue: Issue,
+    pub comment: IssueComment,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckSuitePayload {
+    pub action: CheckSuiteAction,
+    pub check_suite: CheckSuite,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckRunPayload {
+    pub action: CheckRunAction,
+    pub check_run: CheckRun,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct InstallationRepository {
+    pub id: ID,
+    pub node_id: GRID,
+    pub name: String,
+    pub full_name: String,
+    pub private: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct InstallationPayload {
+    pub action: InstallationAction,
+    pub installation: Installation,
+    // in case of deleted installs there is no repository key
+    pub repositories: Option<Vec<InstallationRepository>>,
+    pub sender: User,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct InstallationRepositoriesPayload {
+    pub action: InstallationRepositoriesAction,
+    pub installation: Installation,
+    /// `all` or `selected`
+    pub repository_selection: String,
+    pub repositories_added: Vec<InstallationRepository>,
+    pub repositories_removed: Vec<InstallationRepository>,
+    pub sender: User,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReleasePayload {
+    pub action: ReleaseAction,
+    pub release: Release,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WorkflowRunPayload {
+    pub action: WorkflowRunAction,
+    pub workflow_run: WorkflowRun,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WorkflowJobPayload {
+    pub action: WorkflowJobAction,
+    pub workflow_job: WorkflowJob,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+/// A branch or tag was created.
+#[derive(Deserialize, Debug)]
+pub struct CreatePayload {
+    pub r#ref: String,
+    /// `branch` or `tag`
+    pub ref_type: String,
+    pub master_branch: String,
+    pub description: Option<String>,
+    pub pusher_type: String,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+/// A branch or tag was deleted.
+#[derive(Deserialize, Debug)]
+pub struct DeletePayload {
+    pub r#ref: String,
+    /// `branch` or `tag`
+    pub ref_type: String,
+    pub pusher_type: String,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+/// The status of a commit changed.
+#[derive(Deserialize, Debug)]
+pub struct StatusPayload {
+    pub id: ID,
+    pub sha: CommitSha,
+    /// `pending`, `success`, `failure` or `error`
+    pub state: String,
+    pub context: String,
+    pub description: Option<String>,
+    pub target_url: Option<URI>,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime,
+    #[serde(with = "crate::timestamp")]
+    pub updated_at: DateTime,
+    pub repository: Repository,
+    pub sender: User,
+    pub installation: Option<EventInstallation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENDER: &str =
+        r#"{"id": 1, "login": "octocat", "node_id": "MDQ", "type": "User", "site_admin": false}"#;
+
+    const OWNER: &str = r#"{"login": "Codertocat", "id": 21031067, "node_id": "MDQ6VXNlcjIxMDMxMDY3",
+        "avatar_url": "https://avatars1.githubusercontent.com/u/21031067?v=4", "gravatar_id": "",
+        "url": "https://api.github.com/users/Codertocat", "html_url": "https://github.com/Codertocat",
+        "type": "User", "site_admin": false}"#;
+
+    const APP: &str = r#"{"id": 29310, "slug": "octocoders-linter", "node_id": "MDM6QXBwMjkzMTA=",
+        "owner": {"login": "Octocoders", "id": 38302899, "node_id": "MDEyOk9yZ2FuaXphdGlvbjM4MzAyODk5",
+          "type": "Organization", "site_admin": false},
+        "name": "octocoders-linter", "description": null, "external_url": "https://octocoders.io",
+        "html_url": "https://github.com/apps/octocoders-linter",
+        "created_at": "2019-04-19T19:36:24Z", "updated_at": "2019-04-19T19:36:56Z",
+        "permissions": {"administration": "write", "checks": "write", "contents": "write",
+          "deployments": "write", "issues": "write", "members": "write", "metadata": "read",
+          "organization_administration": "write", "organization_hooks": "write",
+          "organization_plan": "read", "organization_projects": "write",
+          "organization_user_blocking": "write", "pages": "write", "pull_requests": "write",
+          "repository_hooks": "write", "repository_projects": "write", "statuses": "write",
+          "team_discussions": "write", "vulnerability_alerts": "read"},
+        "events": []}"#;
+
+    const PULL_REQUEST_REFS: &str = r#"[{
+        "url": "https://api.github.com/repos/Codertocat/Hello-World/pulls/2",
+        "id": 279147437, "number": 2,
+        "head": {"ref": "changes", "sha": "ec26c3e57ca3a959ca5aad62de7213c562f8c821",
+          "repo": {"id": 186853002, "url": "https://api.github.com/repos/Codertocat/Hello-World", "name": "Hello-World"}},
+        "base": {"ref": "master", "sha": "f95f852bd8fca8fcc58a9a2d6c842781e32a215e",
+          "repo": {"id": 186853002, "url": "https://api.github.com/repos/Codertocat/Hello-World", "name": "Hello-World"}}}]"#;
+
+    const REPOSITORY: &str = r#"{"id": 186853002, "node_id": "MDEwOlJlcG9zaXRvcnkxODY4NTMwMDI=",
+        "name": "Hello-World", "full_name": "Codertocat/Hello-World", "private": false,
+        "owner": {"login": "Codertocat", "id": 21031067, "node_id": "MDQ6VXNlcjIxMDMxMDY3",
+          "type": "User", "site_admin": false},
+        "html_url": "https://github.com/Codertocat/Hello-World", "description": null, "fork": false,
+        "default_branch": "master"}"#;
+
+    fn check_suite() -> String {
+        format!(
+            r#"{{"id": 118578147, "node_id": "MDEwOkNoZWNrU3VpdGUxMTg1NzgxNDc=",
+                "head_branch": "changes", "head_sha": "ec26c3e57ca3a959ca5aad62de7213c562f8c821",
+                "status": "completed", "conclusion": "success",
+                "url": "https://api.github.com/repos/Codertocat/Hello-World/check-suites/118578147",
+                "before": "6113728f27ae82c7b1a177c8d03f9e96e0adf246",
+                "after": "ec26c3e57ca3a959ca5aad62de7213c562f8c821",
+                "pull_requests": {pulls}, "app": {app},
+                "created_at": "2019-05-15T15:20:31Z", "updated_at": "2019-05-15T15:21:14Z",
+                "latest_check_runs_count": 1,
+                "check_runs_url": "https://api.github.com/repos/Codertocat/Hello-World/check-suites/118578147/check-runs",
+                "head_commit": {{"id": "ec26c3e57ca3a959ca5aad62de7213c562f8c821",
+                  "tree_id": "31b122c26a97cf9af023e9ddab94a82c6e77b0ea",
+                  "message": "Update README.md", "timestamp": "2019-05-15T15:20:30Z",
+                  "author": {{"name": "Codertocat", "email": "21031067+Codertocat@users.noreply.github.com"}},
+                  "committer": {{"name": "Codertocat", "email": "21031067+Codertocat@users.noreply.github.com"}}}}}}"#,
+            pulls = PULL_REQUEST_REFS,
+            app = APP
+        )
+    }
+
+    #[test]
+    fn parses_known_events() {
+        let body = format!(
+            r#"{{"action": "edited", "issue": {{"id": 1, "number": 2}},
+                "comment": {{"id": 3, "body": "hi", "user": {sender}}},
+                "repository": {{"full_name": "octocat/Hello-World"}},
+                "sender": {sender}, "installation": {{"id": 42, "node_id": "MDIz"}}}}"#,
+            sender = SENDER
+        );
+
+        let event = WebhookEvent::parse("issue_comment", body.as_bytes()).unwrap();
+
+        assert_eq!(event.event_name(), Some("issue_comment"));
+        match event {
+            WebhookEvent::IssueComment(payload) => {
+                assert_eq!(payload.action, IssueCommentAction::Edited);
+                assert_eq!(payload.comment.body, "hi");
+                assert_eq!(payload.installation.unwrap().id, 42);
+            }
+            other => panic!("expected an issue comment, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_check_suite_events() {
+        let body = format!(
+            r#"{{"action": "completed", "check_suite": {suite},
+                "repository": {repo}, "sender": {sender}}}"#,
+            suite = check_suite(),
+            repo = REPOSITORY,
+            sender = OWNER
+        );
+
+        match WebhookEvent::parse("check_suite", body.as_bytes()).unwrap() {
+            WebhookEvent::CheckSuite(payload) => {
+                assert_eq!(payload.action, CheckSuiteAction::Completed);
+                let suite = payload.check_suite;
+                assert_eq!(suite.pull_requests[0].number, 2);
+                assert_eq!(suite.pull_requests[0].head.r#ref, "changes");
+                assert_eq!(suite.app.description, None);
+                assert!(suite.repository.is_none());
+            }
+            other => panic!("expected a check suite, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_requested_check_suites_without_before() {
+        let suite = check_suite().replace(
+            r#""before": "6113728f27ae82c7b1a177c8d03f9e96e0adf246""#,
+            r#""before": null"#,
+        );
+        let body = format!(
+            r#"{{"action": "requested", "check_suite": {}, "repository": {}, "sender": {}}}"#,
+            suite, REPOSITORY, OWNER
+        );
+
+        match WebhookEvent::parse("check_suite", body.as_bytes()).unwrap() {
+            WebhookEvent::CheckSuite(payload) => assert_eq!(payload.check_suite.before, None),
+            other => panic!("expected a check suite, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_check_run_events() {
+        let body = format!(
+            r#"{{"action": "created", "check_run": {{
+                "id": 128620228, "node_id": "MDg6Q2hlY2tSdW4xMjg2MjAyMjg=",
+                "head_sha": "ec26c3e57ca3a959ca5aad62de7213c562f8c821", "external_id": "",
+                "url": "https://api.github.com/repos/Codertocat/Hello-World/check-runs/128620228",
+                "html_url": "https://github.com/Codertocat/Hello-World/runs/128620228",
+                "details_url": "https://octocoders.io", "status": "queued", "conclusion": null,
+                "started_at": "2019-05-15T15:21:12Z", "completed_at": null,
+                "output": {{"title": null, "summary": null, "text": null, "annotations_count": 0,
+                  "annotations_url": "https://api.github.com/repos/Codertocat/Hello-World/check-runs/128620228/annotations"}},
+                "name": "Octocoders-linter", "check_suite": {suite}, "app": {app},
+                "pull_requests": {pulls}}},
+                "repository": {repo}, "sender": {sender}}}"#,
+            suite = check_suite(),
+            app = APP,
+            pulls = PULL_REQUEST_REFS,
+            repo = REPOSITORY,
+            sender = OWNER
+        );
+
+        match WebhookEvent::parse("check_run", body.as_bytes()).unwrap() {
+            WebhookEvent::CheckRun(payload) => {
+                assert_eq!(payload.action, CheckRunAction::Created);
+                assert_eq!(payload.check_run.completed_at, None);
+                assert_eq!(payload.check_run.pull_requests[0].base.r#ref, "master");
+            }
+            other => panic!("expected a check run, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_pull_request_events() {
+        let body = format!(
+            r#"{{"action": "opened", "number": 2, "pull_request": {{
+                "url": "https://api.github.com/repos/Codertocat/Hello-World/pulls/2",
+                "id": 279147437, "node_id": "MDExOlB1bGxSZXF1ZXN0Mjc5MTQ3NDM3",
+                "html_url": "https://github.com/Codertocat/Hello-World/pull/2",
+                "diff_url": "https://github.com/Codertocat/Hello-World/pull/2.diff",
+                "patch_url": "https://github.com/Codertocat/Hello-World/pull/2.patch",
+                "issue_url": "https://api.github.com/repos/Codertocat/Hello-World/issues/2",
+                "number": 2, "state": "open", "locked": false, "title": "Update the README with new information.",
+                "user": {sender}, "body": "This is a pretty simple change that we need to pull into master.",
+                "created_at": "2019-05-15T15:20:33Z", "updated_at": "2019-05-15T15:20:33Z",
+                "closed_at": null, "merged_at": null, "merge_commit_sha": null,
+                "assignee": null, "assignees": [], "requested_reviewers": [], "requested_teams": [],
+                "labels": [], "milestone": null,
+                "commits_url": "https://api.github.com/repos/Codertocat/Hello-World/pulls/2/commits",
+                "review_comments_url": "https://api.github.com/repos/Codertocat/Hello-World/pulls/2/comments",
+                "review_comment_url": "https://api.github.com/repos/Codertocat/Hello-World/pulls/comments{{/number}}",
+                "comments_url": "https://api.github.com/repos/Codertocat/Hello-World/issues/2/comments",
+                "statuses_url": "https://api.github.com/repos/Codertocat/Hello-World/statuses/ec26c3e57ca3a959ca5aad62de7213c562f8c821",
+                "head": {{"label": "Codertocat:changes", "ref": "changes",
+                  "sha": "ec26c3e57ca3a959ca5aad62de7213c562f8c821", "user": {sender}, "repo": {repo}}},
+                "base": {{"label": "Codertocat:master", "ref": "master",
+                  "sha": "f95f852bd8fca8fcc58a9a2d6c842781e32a215e", "user": {sender}, "repo": {repo}}},
+                "author_association": "OWNER", "draft": false, "merged": false, "mergeable": null,
+                "rebaseable": null, "mergeable_state": "unknown", "merged_by": null, "comments": 0,
+                "review_comments": 0, "maintainer_can_modify": false, "commits": 1, "additions": 1,
+                "deletions": 1, "changed_files": 1}},
+                "repository": {repo}, "sender": {sender}}}"#,
+            repo = REPOSITORY,
+            sender = OWNER
+        );
+
+        match WebhookEvent::parse("pull_request", body.as_bytes()).unwrap() {
+            WebhookEvent::PullRequest(payload) => {
+                assert_eq!(payload.action, PullRequestAction::Opened);
+                assert_eq!(payload.pull_request.number, 2);
+                assert_eq!(payload.repository.full_name, "Codertocat/Hello-World");
+            }
+            other => panic!("expected a pull request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_actions() {
+        let action = |json: &str| serde_json::from_str::<InstallationAction>(json).unwrap();
+
+        assert_eq!(action(r#""suspend""#), InstallationAction::Suspend);
+        let added_later = action(r#""transferred""#);
+        assert_eq!(
+            added_later,
+            InstallationAction::Other("transferred".to_string())
+        );
+        assert_eq!(added_later.to_string(), "transferred");
+        assert_eq!(
+            PullRequestAction::ReadyForReview.as_str(),
+            "ready_for_review"
+        );
+    }
+
+    #[test]
+    fn keeps_unmodelled_fields() {
+        let body = format!(
+            r#"{{"action": "started", "starred_at": "2019-05-15T15:20:40Z",
+                "repository": {{"full_name": "octocat/Hello-World"}}, "sender": {}}}"#,
+            SENDER
+        );
+
+        match WebhookEvent::parse("star", body.as_bytes()).unwrap() {
+            WebhookEvent::Star(payload) => {
+                assert_eq!(payload.action.as_deref(), Some("started"));
+                assert_eq!(payload.fields["starred_at"], "2019-05-15T15:20:40Z");
+            }
+            other => panic!("expected a star event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn falls_back_to_unknown_events() {
+        let event = WebhookEvent::parse("space_launch", br#"{"action": "lifted_off"}"#).unwrap();
+
+        assert_eq!(event.event_name(), None);
+        match event {
+            WebhookEvent::Unknown(payload) => assert_eq!(payload["action"], "lifted_off"),
+            other => panic!("expected an unknown event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_malformed_payloads() {
+        let err = WebhookEvent::parse("push", br#"{"ref": "refs/heads/main"}"#).unwrap_err();
+        assert!(matches!(err, OctokitError::Decode { .. }));
+    }
+}
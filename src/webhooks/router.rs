@@ -0,0 +1,593 @@
+//! Dispatches webhook deliveries to handlers registered per event and action.
+//!
+//! The router does not depend on a web framework: hand it the request
+//! headers and body and answer GitHub with the returned status.
+//!
+//! ```no_run
+//! # async fn run(octokit: octokit::Octokit, headers: http::HeaderMap, body: Vec<u8>) {
+//! use octokit::webhooks::events::{PullRequestAction, WebhookEvent};
+//! use octokit::webhooks::{WebhookContext, WebhookRouter, WebhookVerifier};
+//!
+//! let router = WebhookRouter::new(WebhookVerifier::new().secret("default", "secret"))
+//!     .octokit(octokit)
+//!     .on(PullRequestAction::Opened, |ctx: WebhookContext| async move {
+//!         if let WebhookEvent::PullRequest(payload) = ctx.event() {
+//!             println!("#{} was opened", payload.number);
+//!         }
+//!         Ok(())
+//!     })
+//!     .on("push", |ctx: WebhookContext| async move {
+//!         println!("push to installation {:?}", ctx.event().installation_id());
+//!         Ok(())
+//!     });
+//!
+//! let outcome = router.handle(&headers, &body).await;
+//! for err in &outcome.errors {
+//!     eprintln!("delivery {:?} failed: {}", outcome.delivery_id, err);
+//! }
+//! # }
+//! ```
+
+use futures_util::future::{join_all, BoxFuture, FutureExt};
+use http::{HeaderMap, StatusCode};
+use log::{debug, warn};
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use super::delivery::{DeliveryId, DeliveryStore, WebhookDelivery};
+use super::{WebhookEvent, WebhookVerifier};
+use super::{SIGNATURE_256_HEADER_NAME, SIGNATURE_HEADER_NAME};
+use crate::error::OctokitError;
+use crate::Octokit;
+
+/// Selects the events a handler is called for.
+///
+/// Implemented by the action enums in [`events`](../events/index.html), which
+/// match one action of one event, and by strings of the form `"pull_request"`
+/// (every action) or `"pull_request.opened"`. `"*"` matches every event.
+pub trait EventFilter: Send + Sync {
+    fn accepts(&self, ctx: &WebhookContext) -> bool;
+}
+
+impl EventFilter for &'static str {
+    fn accepts(&self, ctx: &WebhookContext) -> bool {
+        if *self == "*" {
+            return true;
+        }
+        let (name, action) = match self.split_once('.') {
+            Some((name, action)) => (name, Some(action)),
+            None => (*self, None),
+        };
+        ctx.event_name() == name && action.is_none_or(|action| ctx.event().action() == Some(action))
+    }
+}
+
+/// Handles the deliveries matching its filter.
+///
+/// Implemented for async closures taking a [`WebhookContext`](struct.WebhookContext.html).
+pub trait WebhookHandler: Send + Sync {
+    fn call(&self, ctx: WebhookContext) -> BoxFuture<'static, Result<(), OctokitError>>;
+}
+
+impl<F, Fut> WebhookHandler for F
+where
+    F: Fn(WebhookContext) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<(), OctokitError>> + Send + 'static,
+{
+    fn call(&self, ctx: WebhookContext) -> BoxFuture<'static, Result<(), OctokitError>> {
+        self(ctx).boxed()
+    }
+}
+
+/// Runs around the handlers of every verified delivery, e.g. for logging or
+/// to skip deliveries from certain installations.
+pub trait Middleware: Send + Sync {
+    /// Called before the handlers. Returning an error skips the handlers,
+    /// the error is collected like a handler error.
+    fn before(&self, _ctx: &WebhookContext) -> Result<(), OctokitError> {
+        Ok(())
+    }
+
+    /// Called after the handlers with the errors collected so far.
+    fn after(&self, _ctx: &WebhookContext, _errors: &[OctokitError]) {}
+}
+
+/// A verified delivery, passed to every matching handler.
+#[derive(Clone)]
+pub struct WebhookContext {
+    event_name: String,
+    event: Arc<WebhookEvent>,
+    delivery_id: Option<DeliveryId>,
+    octokit: Option<Octokit>,
+}
+
+impl WebhookContext {
+    /// The value of the `X-GitHub-Event` header, also set for events this
+    /// crate does not know about.
+    pub fn event_name(&self) -> &str {
+        &self.event_name
+    }
+
+    pub fn event(&self) -> &WebhookEvent {
+        &self.event
+    }
+
+    /// The value of the `X-GitHub-Delivery` header.
+    pub fn delivery_id(&self) -> Option<&DeliveryId> {
+        self.delivery_id.as_ref()
+    }
+
+    /// A client acting as the installation the event was delivered for.
+    ///
+    /// Falls back to the client passed to the router for events without an
+    /// installation or when it was not built with GitHub App credentials.
+    /// `None` when the router has no client.
+    pub fn octokit(&self) -> Option<&Octokit> {
+        self.octokit.as_ref()
+    }
+}
+
+impl fmt::Debug for WebhookContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookContext")
+            .field("event", &self.event_name)
+            .field("action", &self.event.action())
+            .field("delivery_id", &self.delivery_id)
+            .finish()
+    }
+}
+
+/// What became of a delivery.
+#[derive(Debug)]
+pub struct WebhookOutcome {
+    /// The status to answer GitHub with:
+    ///
+    /// * `400 Bad Request` for deliveries without event header or with a body
+    ///   that does not decode
+    /// * `401 Unauthorized` for deliveries failing signature verification
+    /// * `500 Internal Server Error` when a middleware or handler failed
+    /// * `200 OK` otherwise, even if no handler matched or the delivery was
+    ///   a duplicate
+    pub status: StatusCode,
+    pub delivery_id: Option<DeliveryId>,
+    /// The number of handlers the delivery was passed to.
+    pub handled: usize,
+    /// Whether the delivery was skipped because it was handled before, see
+    /// [`WebhookRouter::deliveries`](struct.WebhookRouter.html#method.deliveries).
+    pub duplicate: bool,
+    pub errors: Vec<OctokitError>,
+}
+
+impl WebhookOutcome {
+    fn rejected(status: StatusCode, delivery_id: Option<DeliveryId>, err: OctokitError) -> Self {
+        warn!("rejecting webhook delivery {:?}: {}", delivery_id, err);
+        WebhookOutcome {
+            status,
+            delivery_id,
+            handled: 0,
+            duplicate: false,
+            errors: vec![err],
+        }
+    }
+
+    fn duplicate(delivery_id: DeliveryId) -> Self {
+        debug!(
+            "skipping webhook delivery {}, it was handled before",
+            delivery_id
+        );
+        WebhookOutcome {
+            status: StatusCode::OK,
+            delivery_id: Some(delivery_id),
+            handled: 0,
+            duplicate: true,
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// Verifies deliveries and dispatches them to the registered handlers.
+pub struct WebhookRouter {
+    verifier: WebhookVerifier,
+    octokit: Option<Octokit>,
+    routes: Vec<(Box<dyn EventFilter>, Box<dyn WebhookHandler>)>,
+    middleware: Vec<Box<dyn Middleware>>,
+    deliveries: Option<Box<dyn DeliveryStore>>,
+}
+
+impl WebhookRouter {
+    /// Accepts the deliveries `verifier` accepts, see
+    /// [`WebhookVerifier`](../struct.WebhookVerifier.html).
+    pub fn new(verifier: WebhookVerifier) -> WebhookRouter {
+        WebhookRouter {
+            verifier,
+            octokit: None,
+            routes: Vec::new(),
+            middleware: Vec::new(),
+            deliveries: None,
+        }
+    }
+
+    /// The client handlers get through [`WebhookContext::octokit`](struct.WebhookContext.html#method.octokit).
+    /// A client built with [`OctokitBuilder::app`](../../struct.OctokitBuilder.html#method.app)
+    /// is scoped to the installation of each delivery.
+    pub fn octokit(mut self, octokit: Octokit) -> Self {
+        self.octokit = Some(octokit);
+        self
+    }
+
+    /// Calls `handler` for the deliveries matching `filter`. All matching
+    /// handlers of a delivery run concurrently.
+    pub fn on(
+        mut self,
+        filter: impl EventFilter + 'static,
+        handler: impl WebhookHandler + 'static,
+    ) -> Self {
+        self.routes.push((Box::new(filter), Box::new(handler)));
+        self
+    }
+
+    /// Adds a middleware, which run in the order they were added.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Skips deliveries `store` has seen before. A delivery is recorded once
+    /// it passed verification and forgotten again if handling it failed, so
+    /// redelivering failed deliveries still works.
+    pub fn deliveries(mut self, store: impl DeliveryStore + 'static) -> Self {
+        self.deliveries = Some(Box::new(store));
+        self
+    }
+
+    /// Verifies, decodes and dispatches a delivery.
+    pub async fn handle(&self, headers: &HeaderMap, body: &[u8]) -> WebhookOutcome {
+        match self.accept(headers, body) {
+            Ok(ctx) => self.dispatch(ctx).await,
+            Err(outcome) => outcome,
+        }
+    }
+
+    /// Verifies and decodes a delivery without calling the handlers yet, so
+    /// the delivery can be acknowledged before it is [`dispatch`](#method.dispatch)ed.
+    /// GitHub gives up on deliveries that are not answered within 10 seconds.
+    ///
+    /// Fails with the outcome to answer with right away for rejected and
+    /// duplicate deliveries.
+    pub fn accept(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<WebhookContext, WebhookOutcome> {
+        let delivery_id = DeliveryId::from_headers(headers);
+        if let Err(err) = self.verifier.verify(
+            header(headers, SIGNATURE_256_HEADER_NAME),
+            header(headers, SIGNATURE_HEADER_NAME),
+            body,
+        ) {
+            return Err(WebhookOutcome::rejected(
+                StatusCode::UNAUTHORIZED,
+                delivery_id,
+                err,
+            ));
+        }
+        let delivery = match WebhookDelivery::parse(headers, body) {
+            Ok(delivery) => delivery,
+            Err(err) => {
+                return Err(WebhookOutcome::rejected(
+                    StatusCode::BAD_REQUEST,
+                    delivery_id,
+                    err,
+                ))
+            }
+        };
+        if let (Some(store), Some(id)) = (&self.deliveries, &delivery.id) {
+            if !store.insert(id) {
+                return Err(WebhookOutcome::duplicate(id.clone()));
+            }
+        }
+        debug!(
+            "received {}.{} delivery {:?}",
+            delivery.event_name,
+            delivery.event.action().unwrap_or(""),
+            delivery.id
+        );
+
+        Ok(WebhookContext {
+            octokit: self.client_for(&delivery.event),
+            event_name: delivery.event_name,
+            event: Arc::new(delivery.event),
+            delivery_id: delivery.id,
+        })
+    }
+
+    /// Runs the middleware and the handlers matching an accepted delivery.
+    pub async fn dispatch(&self, ctx: WebhookContext) -> WebhookOutcome {
+        let mut errors = Vec::new();
+        let mut handled = 0;
+        match self.middleware.iter().try_for_each(|m| m.before(&ctx)) {
+            Ok(()) => {
+                let calls = self
+                    .routes
+                    .iter()
+                    .filter(|(filter, _)| filter.accepts(&ctx))
+                    .map(|(_, handler)| handler.call(ctx.clone()));
+                let results = join_all(calls).await;
+                handled = results.len();
+                errors.extend(results.into_iter().filter_map(Result::err));
+            }
+            Err(err) => errors.push(err),
+        }
+        for middleware in &self.middleware {
+            middleware.after(&ctx, &errors);
+        }
+        if let (Some(store), Some(id), false) =
+            (&self.deliveries, &ctx.delivery_id, errors.is_empty())
+        {
+            store.remove(id);
+        }
+
+        WebhookOutcome {
+            status: if errors.is_empty() {
+                StatusCode::OK
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+            delivery_id: ctx.delivery_id,
+            handled,
+            duplicate: false,
+            errors,
+        }
+    }
+
+    fn client_for(&self, event: &WebhookEvent) -> Option<Octokit> {
+        let octokit = self.octokit.as_ref()?;
+        event
+            .installation_id()
+            .and_then(|id| octokit.for_installation(id).ok())
+            .or_else(|| Some(octokit.clone()))
+    }
+}
+
+impl fmt::Debug for WebhookRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookRouter")
+            .field("verifier", &self.verifier)
+            .field("routes", &self.routes.len())
+            .field("middleware", &self.middleware.len())
+            .finish()
+    }
+}
+
+pub(super) fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{Credentials, PersonalAccessToken};
+    use crate::webhooks::events::{IssueCommentAction, PullRequestAction};
+    use crate::ID;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    const SECRET: &str = "router secret";
+
+    fn delivery(event: &str, body: &[u8]) -> HeaderMap {
+        let signature = crate::webhooks::sign_sha256(SECRET, body);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-github-event", event.parse().unwrap());
+        headers.insert("x-github-delivery", "72d3162e".parse().unwrap());
+        headers.insert("x-hub-signature-256", signature.parse().unwrap());
+        headers
+    }
+
+    fn router() -> WebhookRouter {
+        WebhookRouter::new(WebhookVerifier::new().secret("default", SECRET))
+    }
+
+    fn counting(calls: &Arc<AtomicUsize>) -> impl WebhookHandler {
+        let calls = calls.clone();
+        move |_: WebhookContext| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Ok(()) }
+        }
+    }
+
+    const CREATED_LABEL: &[u8] = br#"{"action": "created", "installation": {"id": 7}}"#;
+
+    #[test]
+    fn accepts_string_filters() {
+        let ctx = WebhookContext {
+            event_name: "label".to_string(),
+            event: Arc::new(WebhookEvent::parse("label", CREATED_LABEL).unwrap()),
+            delivery_id: None,
+            octokit: None,
+        };
+        assert!("label".accepts(&ctx));
+        assert!("label.created".accepts(&ctx));
+        assert!("*".accepts(&ctx));
+        assert!(!"label.deleted".accepts(&ctx));
+        assert!(!"issues".accepts(&ctx));
+        assert!(!PullRequestAction::Closed.accepts(&ctx));
+    }
+
+    fn issue_comment(action: &str) -> Vec<u8> {
+        let user = r#"{"id": 1, "login": "octocat", "node_id": "MDQ", "type": "User", "site_admin": false}"#;
+        format!(
+            r#"{{"action": "{action}", "issue": {{"id": 1, "number": 2}},
+                "comment": {{"id": 3, "body": "hi", "user": {user}}},
+                "repository": {{"full_name": "octocat/Hello-World"}}, "sender": {user}}}"#,
+            action = action,
+            user = user
+        )
+        .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn dispatches_by_event_and_action() {
+        let (created, edited, any) = Default::default();
+        let router = router()
+            .on(IssueCommentAction::Created, counting(&created))
+            .on(IssueCommentAction::Edited, counting(&edited))
+            .on("unknown_event", counting(&any));
+
+        let body = issue_comment("created");
+        let outcome = router
+            .handle(&delivery("issue_comment", &body), &body)
+            .await;
+        assert_eq!(outcome.status, StatusCode::OK);
+        assert_eq!(outcome.delivery_id, Some(DeliveryId::from("72d3162e")));
+        assert_eq!(outcome.handled, 1);
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        assert_eq!(edited.load(Ordering::SeqCst), 0);
+
+        let body = issue_comment("deleted");
+        let outcome = router
+            .handle(&delivery("issue_comment", &body), &body)
+            .await;
+        assert_eq!(outcome.status, StatusCode::OK);
+        assert_eq!(outcome.handled, 0);
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        assert_eq!(edited.load(Ordering::SeqCst), 0);
+
+        let body = br#"{"action": "created", "installation": {"id": 7}}"#;
+        let outcome = router.handle(&delivery("unknown_event", body), body).await;
+        assert_eq!(outcome.handled, 1);
+        assert_eq!(any.load(Ordering::SeqCst), 1);
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_unverified_deliveries() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router().on("*", counting(&calls));
+
+        let mut headers = delivery("label", CREATED_LABEL);
+        let outcome = router.handle(&headers, b"{}").await;
+        assert_eq!(outcome.status, StatusCode::UNAUTHORIZED);
+
+        headers.remove("x-github-event");
+        let outcome = router.handle(&headers, CREATED_LABEL).await;
+        assert_eq!(outcome.status, StatusCode::BAD_REQUEST);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn collects_handler_errors() {
+        struct Skip;
+        impl Middleware for Skip {
+            fn before(&self, ctx: &WebhookContext) -> Result<(), OctokitError> {
+                match ctx.event().installation_id() {
+                    Some(13) => Err(OctokitError::new("installation 13 is blocked")),
+                    _ => Ok(()),
+                }
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router().middleware(Skip).on("label", counting(&calls)).on(
+            "label.created",
+            |ctx: WebhookContext| async move {
+                assert!(ctx.octokit().is_none());
+                Err(OctokitError::new("handler failed"))
+            },
+        );
+
+        let outcome = router
+            .handle(&delivery("label", CREATED_LABEL), CREATED_LABEL)
+            .await;
+        assert_eq!(outcome.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(outcome.handled, 2);
+        assert_eq!(outcome.errors.len(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let blocked = br#"{"action": "created", "installation": {"id": 13}}"#;
+        let outcome = router.handle(&delivery("label", blocked), blocked).await;
+        assert_eq!(outcome.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(outcome.handled, 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn scopes_clients_to_the_installation() {
+        struct App;
+        impl Credentials for App {
+            fn authorization<'a>(
+                &'a self,
+                _: &'a Octokit,
+            ) -> BoxFuture<'a, Result<String, OctokitError>> {
+                futures_util::future::ready(Ok("Bearer app".to_string())).boxed()
+            }
+            fn identity(&self) -> String {
+                "app".to_string()
+            }
+            fn for_installation(&self, id: ID) -> Option<Arc<dyn Credentials>> {
+                Some(Arc::new(PersonalAccessToken(format!(
+                    "installation-{}",
+                    id
+                ))))
+            }
+        }
+
+        let mock = mockito::mock("DELETE", "/repos/octocat/router/issues/comments/1")
+            .match_header("authorization", "token installation-7")
+            .with_status(204)
+            .create();
+        let octokit = crate::testing::mock_builder()
+            .credentials(App)
+            .build()
+            .unwrap();
+        let router =
+            router()
+                .octokit(octokit)
+                .on("label.created", |ctx: WebhookContext| async move {
+                    let octokit = ctx.octokit().unwrap();
+                    octokit.delete_issue_comment("octocat/router", 1).await
+                });
+
+        let outcome = router
+            .handle(&delivery("label", CREATED_LABEL), CREATED_LABEL)
+            .await;
+        assert!(outcome.errors.is_empty(), "{:?}", outcome.errors);
+        assert_eq!(outcome.handled, 1);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn skips_duplicate_deliveries() {
+        use crate::webhooks::delivery::InMemoryDeliveryStore;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let failing = Arc::new(AtomicUsize::new(0));
+        let router = router()
+            .deliveries(InMemoryDeliveryStore::new(Duration::from_secs(60)))
+            .on("label", counting(&calls))
+            .on("label.deleted", {
+                let failing = failing.clone();
+                move |_: WebhookContext| {
+                    failing.fetch_add(1, Ordering::SeqCst);
+                    async { Err(OctokitError::new("handler failed")) }
+                }
+            });
+
+        let headers = delivery("label", CREATED_LABEL);
+        assert!(!router.handle(&headers, CREATED_LABEL).await.duplicate);
+        let outcome = router.handle(&headers, CREATED_LABEL).await;
+        assert!(outcome.duplicate);
+        assert_eq!(outcome.status, StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // failed deliveries are handled again when they are redelivered
+        let deleted = br#"{"action": "deleted"}"#;
+        let mut headers = delivery("label", deleted);
+        headers.insert("x-github-delivery", "a0b1c2d3".parse().unwrap());
+        for _ in 0..2 {
+            let outcome = router.handle(&headers, deleted).await;
+            assert_eq!(outcome.status, StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        assert_eq!(failing.load(Ordering::SeqCst), 2);
+    }
+}
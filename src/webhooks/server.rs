@@ -0,0 +1,261 @@
+//! A hyper service receiving webhook deliveries, enabled by the `server` feature.
+//!
+//! Deliveries are verified and decoded before they are answered, the
+//! handlers run in the background afterwards: GitHub only waits 10 seconds
+//! for an answer and does not care about the outcome of the handlers.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), octokit::error::OctokitError> {
+//! use octokit::webhooks::{server, WebhookContext, WebhookRouter, WebhookVerifier};
+//!
+//! let secret = std::env::var("WEBHOOK_SECRET").unwrap();
+//! let router = WebhookRouter::new(WebhookVerifier::new().secret("default", secret))
+//!     .on("issues.opened", |ctx: WebhookContext| async move {
+//!         println!("new issue in delivery {:?}", ctx.delivery_id());
+//!         Ok(())
+//!     });
+//!
+//! server::serve(([0, 0, 0, 0], 3000).into(), router).await
+//! # }
+//! ```
+//!
+//! Only a hyper 0.14 [`Service`](https://docs.rs/hyper/0.14/hyper/service/trait.Service.html)
+//! ships, there are no dedicated axum or actix adapters to keep those
+//! frameworks out of the dependency tree. The service can be mounted into
+//! an existing hyper server, or into an axum 0.6 router with `route_service`
+//! since it is a tower service. Other frameworks, actix included, convert
+//! their request into a hyper one and call
+//! [`WebhookService::respond`](struct.WebhookService.html#method.respond).
+//!
+//! Signatures are checked with the router's
+//! [`WebhookVerifier`](../struct.WebhookVerifier.html) rather than
+//! [`verify_payload_signature`](../fn.verify_payload_signature.html): it
+//! prefers the SHA-256 header and accepts rotated secrets, while the latter
+//! only remains for callers verifying a single header themselves.
+
+use futures_util::future::{BoxFuture, FutureExt};
+use hyper::body::HttpBody;
+use hyper::server::conn::AddrStream;
+use hyper::service::Service;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::warn;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use super::router::{WebhookOutcome, WebhookRouter};
+use crate::error::OctokitError;
+
+/// GitHub does not send payloads larger than 25 MB.
+const MAX_BODY_SIZE: usize = 25 * 1024 * 1024;
+
+/// Answers deliveries with `202 Accepted` once they passed verification and
+/// dispatches them to the router in the background. Rejected deliveries are
+/// answered with the status of [`WebhookOutcome`](../router/struct.WebhookOutcome.html).
+///
+/// Cloning is cheap, clones share the router.
+#[derive(Clone, Debug)]
+pub struct WebhookService {
+    router: Arc<WebhookRouter>,
+    max_body_size: usize,
+}
+
+impl WebhookService {
+    pub fn new(router: WebhookRouter) -> WebhookService {
+        WebhookService {
+            router: Arc::new(router),
+            max_body_size: MAX_BODY_SIZE,
+        }
+    }
+
+    /// Answers deliveries with larger bodies with `413 Payload Too Large`,
+    /// 25 MB by default.
+    pub fn max_body_size(mut self, limit: usize) -> Self {
+        self.max_body_size = limit;
+        self
+    }
+
+    /// Answers a single delivery, regardless of the request path.
+    ///
+    /// Must be called within a tokio runtime, which runs the handlers.
+    pub async fn respond(&self, request: Request<Body>) -> Response<Body> {
+        if request.method() != Method::POST {
+            return status(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "webhooks are delivered with POST",
+            );
+        }
+        let (parts, body) = request.into_parts();
+        let body = match self.read_body(body).await {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+
+        let ctx = match self.router.accept(&parts.headers, &body) {
+            Ok(ctx) => ctx,
+            Err(outcome) => return answer(outcome),
+        };
+        let router = self.router.clone();
+        tokio::spawn(async move {
+            let outcome = router.dispatch(ctx).await;
+            for err in &outcome.errors {
+                warn!(
+                    "handling delivery {:?} failed: {}",
+                    outcome.delivery_id, err
+                );
+            }
+        });
+        status(StatusCode::ACCEPTED, "")
+    }
+
+    /// Buffers the body, giving up as soon as it exceeds the limit: chunked
+    /// bodies do not announce their size, and nothing is authenticated yet.
+    async fn read_body(&self, mut body: Body) -> Result<Vec<u8>, Response<Body>> {
+        let too_large = || status(StatusCode::PAYLOAD_TOO_LARGE, "payload too large");
+        if body.size_hint().lower() > self.max_body_size as u64 {
+            return Err(too_large());
+        }
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|err| status(StatusCode::BAD_REQUEST, &err.to_string()))?;
+            if bytes.len() + chunk.len() > self.max_body_size {
+                return Err(too_large());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+}
+
+impl Service<Request<Body>> for WebhookService {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let service = self.clone();
+        async move { Ok(service.respond(request).await) }.boxed()
+    }
+}
+
+/// Listens on `addr` and answers every POST request with the router,
+/// until the server fails.
+pub async fn serve(addr: SocketAddr, router: WebhookRouter) -> Result<(), OctokitError> {
+    let service = WebhookService::new(router);
+    let make_service = hyper::service::make_service_fn(move |_: &AddrStream| {
+        let service = service.clone();
+        async move { Ok::<_, Infallible>(service) }
+    });
+    let server = Server::try_bind(&addr).map_err(server_error)?;
+    server.serve(make_service).await.map_err(server_error)
+}
+
+fn server_error(err: hyper::Error) -> OctokitError {
+    OctokitError::new(&format!("webhook server failed: {}", err))
+}
+
+/// Answers rejected and duplicate deliveries.
+fn answer(outcome: WebhookOutcome) -> Response<Body> {
+    let message = outcome
+        .errors
+        .first()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    status(outcome.status, &message)
+}
+
+fn status(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webhooks::{sign_sha256, WebhookContext, WebhookVerifier};
+    use tokio::sync::mpsc;
+
+    const SECRET: &str = "server secret";
+    const BODY: &str = r#"{"action": "created", "installation": {"id": 7}}"#;
+
+    fn delivery(signature: &str) -> Request<Body> {
+        Request::post("/webhooks")
+            .header("X-GitHub-Event", "label")
+            .header("X-GitHub-Delivery", "f1a6e3b0")
+            .header("X-Hub-Signature-256", signature)
+            .body(Body::from(BODY))
+            .unwrap()
+    }
+
+    fn service(deliveries: mpsc::UnboundedSender<String>) -> WebhookService {
+        let router = WebhookRouter::new(WebhookVerifier::new().secret("default", SECRET)).on(
+            "label.created",
+            move |ctx: WebhookContext| {
+                let deliveries = deliveries.clone();
+                async move {
+                    deliveries
+                        .send(ctx.delivery_id().unwrap().to_string())
+                        .unwrap();
+                    Ok(())
+                }
+            },
+        );
+        WebhookService::new(router)
+    }
+
+    #[tokio::test]
+    async fn accepts_and_dispatches_deliveries() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut service = service(tx);
+
+        let response = service
+            .call(delivery(&sign_sha256(SECRET, BODY.as_bytes())))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(rx.recv().await.as_deref(), Some("f1a6e3b0"));
+    }
+
+    #[tokio::test]
+    async fn rejects_unsigned_deliveries() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let service = service(tx);
+
+        let response = service.respond(delivery(&sign_sha256(SECRET, b"{}"))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = service
+            .respond(Request::get("/webhooks").body(Body::empty()).unwrap())
+            .await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        drop(service);
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_chunked_bodies() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let service = service(tx).max_body_size(16);
+        let (mut sender, body) = Body::channel();
+        let request = Request::post("/webhooks")
+            .header("X-GitHub-Event", "label")
+            .body(body)
+            .unwrap();
+        // the body never ends, so the limit has to be enforced while reading
+        tokio::spawn(async move { while sender.send_data("0123456789".into()).await.is_ok() {} });
+
+        let response =
+            tokio::time::timeout(std::time::Duration::from_secs(5), service.respond(request))
+                .await
+                .expect("kept reading past the limit");
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}